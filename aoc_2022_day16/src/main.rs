@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs::read_to_string;
 
+use shortest_path::AdjacencyMatrix;
+
 type LabelBad = (char, char);
 type Label = usize;
 
@@ -81,33 +83,21 @@ fn parse_input(input: &str) -> (Label, HashMap<Label, Valve>) {
     (*start_node, output)
 }
 
+// Every tunnel takes a minute, so this is all-pairs shortest paths with unit
+// weights, flattened so that the distance from i to j is at i * n + j.
 fn floyd_warshall(graph: &HashMap<Label, Valve>) -> Vec<usize> {
-    let mut dist: HashMap<(Label, Label), usize> = Default::default();
+    let num_vertices = graph.len();
+    let mut tunnels = AdjacencyMatrix::new(num_vertices);
     for (label_k, valve_k) in graph.iter() {
-        dist.insert((*label_k, *label_k), 0);
         for neighbor in valve_k.exits.iter() {
-            dist.insert((*label_k, *neighbor), 1);
-        }
-    }
-    for k in graph.keys() {
-        for i in graph.keys() {
-            for j in graph.keys() {
-                if dist.contains_key(&(*i, *k)) && dist.contains_key(&(*k, *j)) {
-                    let ikj_weight = dist.get(&(*i, *k)).unwrap() + dist.get(&(*k, *j)).unwrap();
-                    if !dist.contains_key(&(*i, *j)) || (dist.get(&(*i, *j)).unwrap() > &ikj_weight)
-                    {
-                        dist.insert((*i, *j), ikj_weight);
-                    }
-                }
-            }
+            tunnels.set_edge(*label_k, *neighbor, 1usize);
         }
     }
-    let num_vertices = graph.len();
+    let dist = shortest_path::floyd_warshall(&tunnels, num_vertices).unwrap();
     let mut output: Vec<usize> = vec![0; num_vertices * num_vertices];
-    //    println!("I made a vec of length {}", output.len());
     for i in 0..num_vertices {
         for j in 0..num_vertices {
-            output[(i * num_vertices) + j] = *dist.get(&(i, j)).unwrap();
+            output[(i * num_vertices) + j] = *dist.distance(i, j).unwrap();
         }
     }
     output
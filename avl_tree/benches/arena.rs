@@ -0,0 +1,120 @@
+// Times the boxed AvlMap, the ArenaMap and std's BTreeMap on the same inserts,
+// lookups and removes. There is no benchmarking crate here, so this is a plain
+// program: run it with `cargo bench` and read the table.
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use avl_tree::{ArenaMap, AvlMap};
+
+const N: u64 = 200_000;
+const ROUNDS: u32 = 5;
+
+// Each workload gets the same shuffled keys, so nobody benefits from
+// inserting in order.
+fn keys() -> Vec<u64> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    (0..N)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+// What every map has to be able to do for us to time it.
+trait Workload: Default {
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&self, key: &u64) -> Option<&u64>;
+    fn remove(&mut self, key: &u64) -> Option<u64>;
+}
+
+impl Workload for AvlMap<u64, u64> {
+    fn insert(&mut self, key: u64, value: u64) {
+        AvlMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &u64) -> Option<&u64> {
+        AvlMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &u64) -> Option<u64> {
+        AvlMap::remove(self, key)
+    }
+}
+
+impl Workload for ArenaMap<u64, u64> {
+    fn insert(&mut self, key: u64, value: u64) {
+        ArenaMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &u64) -> Option<&u64> {
+        ArenaMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &u64) -> Option<u64> {
+        ArenaMap::remove(self, key)
+    }
+}
+
+impl Workload for BTreeMap<u64, u64> {
+    fn insert(&mut self, key: u64, value: u64) {
+        BTreeMap::insert(self, key, value);
+    }
+
+    fn get(&self, key: &u64) -> Option<&u64> {
+        BTreeMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &u64) -> Option<u64> {
+        BTreeMap::remove(self, key)
+    }
+}
+
+// The best of a few rounds for each phase, which is the least noisy number.
+fn run<M: Workload>(name: &str, keys: &[u64]) {
+    let mut best = [Duration::MAX; 3];
+    for _ in 0..ROUNDS {
+        let mut map = M::default();
+        let start = Instant::now();
+        for &key in keys {
+            map.insert(key, key);
+        }
+        let inserted = Instant::now();
+        for key in keys {
+            black_box(map.get(key));
+        }
+        let looked_up = Instant::now();
+        for key in keys {
+            black_box(map.remove(key));
+        }
+        let removed = Instant::now();
+        let times = [inserted - start, looked_up - inserted, removed - looked_up];
+        for (best, time) in best.iter_mut().zip(times) {
+            *best = (*best).min(time);
+        }
+    }
+    let per_op = |time: Duration| time.as_nanos() as f64 / keys.len() as f64;
+    println!(
+        "{:<10} {:>10.1} {:>10.1} {:>10.1}",
+        name,
+        per_op(best[0]),
+        per_op(best[1]),
+        per_op(best[2])
+    );
+}
+
+fn main() {
+    let keys = keys();
+    println!("{} keys, ns per operation, best of {}", N, ROUNDS);
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}",
+        "", "insert", "lookup", "remove"
+    );
+    run::<AvlMap<u64, u64>>("boxed", &keys);
+    run::<ArenaMap<u64, u64>>("arena", &keys);
+    run::<BTreeMap<u64, u64>>("btreemap", &keys);
+}
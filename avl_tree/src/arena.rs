@@ -0,0 +1,1338 @@
+// The same AVL tree again, but with every node in one Vec and the links
+// between them as u32 indices instead of Boxes. Inserting is one push onto the
+// Vec (or reusing a freed slot) rather than one allocation, the nodes sit
+// close together in memory, and a link is four bytes rather than eight.
+//
+// Freed slots form a linked list threaded through the slots themselves, so
+// removing never shifts anything and the next insert reuses the slot.
+//
+// Nodes keep an i64 height and a subtree size like AVLNode does, so that
+// comparing the two is a comparison of Box against index and not of field
+// widths.
+
+use std::borrow::Borrow;
+use std::cmp::{max, Ordering};
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::RangeBounds;
+use std::vec;
+
+use crate::check::Violation;
+use crate::iter::{projected_iterator, walk_iterator, Parts, Walk};
+
+// Where a missing child points.
+const NIL: u32 = u32::MAX;
+
+#[derive(Debug)]
+struct ArenaNode<K, V> {
+    label: K,
+    value: V,
+    height: i64,
+    // How many nodes are in this subtree, counting this one.
+    size: usize,
+    left: u32,
+    right: u32,
+}
+
+#[derive(Debug)]
+enum Slot<K, V> {
+    Used(ArenaNode<K, V>),
+    // The next free slot, or NIL.
+    Free(u32),
+}
+
+/// An ordered map backed by an AVL tree whose nodes all live in one `Vec`.
+/// It has the same API as a plain `AvlMap<K, V>`, less the set operations
+/// and the drawing, so code can switch between the two.
+#[derive(Debug)]
+pub struct ArenaMap<K, V> {
+    slots: Vec<Slot<K, V>>,
+    root: u32,
+    free: u32,
+    len: usize,
+}
+
+impl<K, V> ArenaMap<K, V> {
+    pub fn new() -> Self {
+        ArenaMap {
+            slots: vec![],
+            root: NIL,
+            free: NIL,
+            len: 0,
+        }
+    }
+
+    /// An empty map with room for `capacity` entries before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArenaMap {
+            slots: Vec::with_capacity(capacity),
+            ..ArenaMap::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = ArenaMap::new();
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height_of(self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            walk: Walk::new(self.cursor(self.root)),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        // A walk has to read the links out of the slots as it goes, and the
+        // borrow checker won't let it do that while it hands out &mut to the
+        // values in those same slots. So we line the values up in key order
+        // first, which costs O(n) memory up front rather than O(height).
+        let order = self.in_order();
+        let mut entries: Vec<Option<(&K, &mut V)>> = self
+            .slots
+            .iter_mut()
+            .map(|slot| match slot {
+                Slot::Used(node) => Some((&node.label, &mut node.value)),
+                Slot::Free(_) => None,
+            })
+            .collect();
+        let in_order: Vec<(&K, &mut V)> = order
+            .into_iter()
+            .map(|index| entries[index as usize].take().unwrap())
+            .collect();
+        IterMut {
+            entries: in_order.into_iter(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Iterates over the entries whose keys fall in `range`, in key order. A
+    /// range whose start is past its end is simply empty.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range {
+            walk: Walk::range(self.cursor(self.root), split_ref, &range),
+        }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entry(self.min_of(self.root))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entry(self.max_of(self.root))
+    }
+
+    /// The entry with the greatest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut best = NIL;
+        let mut current = self.root;
+        while current != NIL {
+            let node = self.node(current);
+            if node.label.borrow() <= key {
+                best = current;
+                current = node.right;
+            } else {
+                current = node.left;
+            }
+        }
+        self.entry(best)
+    }
+
+    /// The entry with the smallest key greater than or equal to `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut best = NIL;
+        let mut current = self.root;
+        while current != NIL {
+            let node = self.node(current);
+            if node.label.borrow() >= key {
+                best = current;
+                current = node.left;
+            } else {
+                current = node.right;
+            }
+        }
+        self.entry(best)
+    }
+
+    /// The number of keys strictly less than `key`, whether or not `key` is
+    /// itself in the map.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut smaller = 0;
+        let mut current = self.root;
+        while current != NIL {
+            let node = self.node(current);
+            if key <= node.label.borrow() {
+                current = node.left;
+            } else {
+                smaller += self.size_of(node.left) + 1;
+                current = node.right;
+            }
+        }
+        smaller
+    }
+
+    /// The entry with the `index`-th smallest key, counting from zero.
+    pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+        let mut index = index;
+        let mut current = self.root;
+        while current != NIL {
+            let node = self.node(current);
+            let left_size = self.size_of(node.left);
+            match index.cmp(&left_size) {
+                Ordering::Less => current = node.left,
+                Ordering::Equal => return self.entry(current),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    current = node.right;
+                }
+            }
+        }
+        None
+    }
+
+    fn node(&self, index: u32) -> &ArenaNode<K, V> {
+        match &self.slots[index as usize] {
+            Slot::Used(node) => node,
+            Slot::Free(_) => panic!("Slot {} is free but something still links to it.", index),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut ArenaNode<K, V> {
+        match &mut self.slots[index as usize] {
+            Slot::Used(node) => node,
+            Slot::Free(_) => panic!("Slot {} is free but something still links to it.", index),
+        }
+    }
+
+    fn entry(&self, index: u32) -> Option<(&K, &V)> {
+        if index == NIL {
+            return None;
+        }
+        let node = self.node(index);
+        Some((&node.label, &node.value))
+    }
+
+    // A subtree for a walk to start from.
+    fn cursor(&self, index: u32) -> Option<Cursor<'_, K, V>> {
+        if index == NIL {
+            None
+        } else {
+            Some((self.slots.as_slice(), index))
+        }
+    }
+
+    // Every node's index, in key order.
+    fn in_order(&self) -> Vec<u32> {
+        let mut order = Vec::with_capacity(self.len);
+        let mut spine = vec![];
+        let mut current = self.root;
+        while current != NIL || !spine.is_empty() {
+            while current != NIL {
+                spine.push(current);
+                current = self.node(current).left;
+            }
+            let index = spine.pop().unwrap();
+            order.push(index);
+            current = self.node(index).right;
+        }
+        order
+    }
+
+    fn min_of(&self, index: u32) -> u32 {
+        let mut current = index;
+        while current != NIL && self.node(current).left != NIL {
+            current = self.node(current).left;
+        }
+        current
+    }
+
+    fn max_of(&self, index: u32) -> u32 {
+        let mut current = index;
+        while current != NIL && self.node(current).right != NIL {
+            current = self.node(current).right;
+        }
+        current
+    }
+
+    fn height_of(&self, index: u32) -> i64 {
+        if index == NIL {
+            0
+        } else {
+            self.node(index).height
+        }
+    }
+
+    fn size_of(&self, index: u32) -> usize {
+        if index == NIL {
+            0
+        } else {
+            self.node(index).size
+        }
+    }
+
+    fn balance_factor(&self, index: u32) -> i64 {
+        if index == NIL {
+            return 0;
+        }
+        let node = self.node(index);
+        self.height_of(node.left) - self.height_of(node.right)
+    }
+
+    fn allocate(&mut self, label: K, value: V) -> u32 {
+        let node = ArenaNode {
+            label,
+            value,
+            height: 1,
+            size: 1,
+            left: NIL,
+            right: NIL,
+        };
+        if self.free != NIL {
+            let index = self.free;
+            self.free = match mem::replace(&mut self.slots[index as usize], Slot::Used(node)) {
+                Slot::Free(next) => next,
+                Slot::Used(_) => panic!("Slot {} is on the free list but in use.", index),
+            };
+            return index;
+        }
+        let index = self.slots.len();
+        assert!(
+            index < NIL as usize,
+            "an ArenaMap can only hold u32::MAX entries"
+        );
+        self.slots.push(Slot::Used(node));
+        index as u32
+    }
+
+    fn release(&mut self, index: u32) -> ArenaNode<K, V> {
+        let freed = mem::replace(&mut self.slots[index as usize], Slot::Free(self.free));
+        self.free = index;
+        match freed {
+            Slot::Used(node) => node,
+            Slot::Free(_) => panic!("Slot {} was freed twice.", index),
+        }
+    }
+
+    // Height and size both come from the children, so like node::refresh this
+    // only works if theirs are right.
+    fn refresh(&mut self, index: u32) {
+        let node = self.node(index);
+        let height = 1 + max(self.height_of(node.left), self.height_of(node.right));
+        let size = 1 + self.size_of(node.left) + self.size_of(node.right);
+        let node = self.node_mut(index);
+        node.height = height;
+        node.size = size;
+    }
+
+    // Like node::rotate_left, but it hands back the new root of the subtree
+    // instead of writing it through a &mut, since the parent's link is just a
+    // number it can set itself.
+    fn rotate_left(&mut self, old_root: u32) -> u32 {
+        let new_root = self.node(old_root).right;
+        // We only ever rotate toward the shorter side, so the right child we are
+        // bringing up is the taller one and can't be missing.
+        if new_root == NIL {
+            unreachable!("rotate_left: no right child to bring up");
+        }
+        self.node_mut(old_root).right = self.node(new_root).left;
+        self.node_mut(new_root).left = old_root;
+        self.refresh(old_root);
+        self.refresh(new_root);
+        new_root
+    }
+
+    fn rotate_right(&mut self, old_root: u32) -> u32 {
+        let new_root = self.node(old_root).left;
+        // We only ever rotate toward the shorter side, so the left child we are
+        // bringing up is the taller one and can't be missing.
+        if new_root == NIL {
+            unreachable!("rotate_right: no left child to bring up");
+        }
+        self.node_mut(old_root).left = self.node(new_root).right;
+        self.node_mut(new_root).right = old_root;
+        self.refresh(old_root);
+        self.refresh(new_root);
+        new_root
+    }
+
+    fn rebalance(&mut self, index: u32) -> u32 {
+        self.refresh(index); // we could be balanced but still wrong
+        match self.balance_factor(index) {
+            -1..=1 => index,
+            -2 => {
+                let right = self.node(index).right;
+                if self.balance_factor(right) > 0 {
+                    self.node_mut(index).right = self.rotate_right(right);
+                }
+                self.rotate_left(index)
+            }
+            2 => {
+                let left = self.node(index).left;
+                if self.balance_factor(left) < 0 {
+                    self.node_mut(index).left = self.rotate_left(left);
+                }
+                self.rotate_right(index)
+            }
+            // Both children were balanced before this one insert or delete,
+            // which moves a height by at most one, so we can only be off by two.
+            x => unreachable!("rebalance: balance factor {} after one change", x),
+        }
+    }
+
+    // Cuts the smallest node out of the subtree without freeing it. Returns
+    // the subtree's new root and the node that was cut out.
+    fn detach_min(&mut self, index: u32) -> (u32, u32) {
+        let left = self.node(index).left;
+        if left == NIL {
+            return (self.node(index).right, index);
+        }
+        let (new_left, min) = self.detach_min(left);
+        self.node_mut(index).left = new_left;
+        (self.rebalance(index), min)
+    }
+
+    // The mirror image of detach_min.
+    fn detach_max(&mut self, index: u32) -> (u32, u32) {
+        let right = self.node(index).right;
+        if right == NIL {
+            return (self.node(index).left, index);
+        }
+        let (new_right, max) = self.detach_max(right);
+        self.node_mut(index).right = new_right;
+        (self.rebalance(index), max)
+    }
+
+    // Hangs left and right either side of pivot, a node that is in neither of
+    // them, and returns the new root. Like join::join it walks down the taller
+    // tree's spine until the heights are close, so it costs O(difference in
+    // heights).
+    fn join_nodes(&mut self, left: u32, pivot: u32, right: u32) -> u32 {
+        let left_height = self.height_of(left);
+        let right_height = self.height_of(right);
+        if left_height > right_height + 1 {
+            let spine = self.node(left).right;
+            self.node_mut(left).right = self.join_nodes(spine, pivot, right);
+            self.rebalance(left)
+        } else if right_height > left_height + 1 {
+            let spine = self.node(right).left;
+            self.node_mut(right).left = self.join_nodes(left, pivot, spine);
+            self.rebalance(right)
+        } else {
+            let node = self.node_mut(pivot);
+            node.left = left;
+            node.right = right;
+            self.refresh(pivot);
+            pivot
+        }
+    }
+
+    // Like join_nodes, but without a pivot: we borrow the largest node of left.
+    fn join_without_pivot(&mut self, left: u32, right: u32) -> u32 {
+        if left == NIL {
+            return right;
+        }
+        let (left, max) = self.detach_max(left);
+        self.join_nodes(left, max, right)
+    }
+
+    // Moves the subtree at index out of from's slots and into ours, shape and
+    // all, and returns where its root ended up. Fixing up either len is left
+    // to the caller.
+    fn adopt(&mut self, from: &mut Self, index: u32) -> u32 {
+        if index == NIL {
+            return NIL;
+        }
+        let node = from.release(index);
+        let left = self.adopt(from, node.left);
+        let right = self.adopt(from, node.right);
+        let adopted = self.allocate(node.label, node.value);
+        let moved = self.node_mut(adopted);
+        moved.height = node.height;
+        moved.size = node.size;
+        moved.left = left;
+        moved.right = right;
+        adopted
+    }
+}
+
+impl<K: Ord, V> ArenaMap<K, V> {
+    /// Makes sure the keys are in order, every node is in balance, and every
+    /// height and size is up to date. Returns the first problem it finds.
+    pub fn check(&self) -> Result<(), Violation> {
+        self.check_subtree(self.root, 0, None, None).map(|_| ())
+    }
+
+    // The same walk as check::check_subtree, less the summaries.
+    fn check_subtree<'a>(
+        &'a self,
+        index: u32,
+        offset: usize,
+        lower: Option<&'a K>,
+        upper: Option<&'a K>,
+    ) -> Result<(i64, usize), Violation> {
+        if index == NIL {
+            return Ok((0, 0));
+        }
+        let node = self.node(index);
+        let (left_height, left_size) =
+            self.check_subtree(node.left, offset, lower, Some(&node.label))?;
+        let position = offset + left_size;
+        let above_lower = lower.is_none_or(|lower| *lower < node.label);
+        let below_upper = upper.is_none_or(|upper| node.label < *upper);
+        if !above_lower || !below_upper {
+            return Err(Violation::OutOfOrder { position });
+        }
+        let (right_height, right_size) =
+            self.check_subtree(node.right, position + 1, Some(&node.label), upper)?;
+
+        let balance_factor = left_height - right_height;
+        if !(-1..=1).contains(&balance_factor) {
+            return Err(Violation::Unbalanced {
+                position,
+                balance_factor,
+            });
+        }
+        let height = 1 + max(left_height, right_height);
+        if height != node.height {
+            return Err(Violation::StaleHeight {
+                position,
+                stored: node.height,
+                correct: height,
+            });
+        }
+        let size = 1 + left_size + right_size;
+        if size != node.size {
+            return Err(Violation::StaleSize {
+                position,
+                stored: node.size,
+                correct: size,
+            });
+        }
+        Ok((height, size))
+    }
+
+    /// Panics with whatever `check` finds wrong, or if the tree and the free
+    /// list between them do not account for every slot.
+    pub fn validate(&self) {
+        if let Err(violation) = self.check() {
+            panic!("{}", violation);
+        }
+        let reachable = self.size_of(self.root);
+        if reachable != self.len {
+            panic!(
+                "My len should say {} but instead it says {}",
+                reachable, self.len
+            );
+        }
+        let mut free = 0;
+        let mut current = self.free;
+        while current != NIL {
+            current = match &self.slots[current as usize] {
+                Slot::Free(next) => *next,
+                Slot::Used(_) => panic!("Slot {} is on the free list but in use.", current),
+            };
+            free += 1;
+        }
+        if reachable + free != self.slots.len() {
+            panic!(
+                "{} slots are in the tree and {} are free, but there are {}",
+                reachable,
+                free,
+                self.slots.len()
+            );
+        }
+    }
+
+    /// Inserts a key and value, returning the value previously stored under
+    /// an equal key. The existing key is kept in that case.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (root, old_value) = self.insert_into(self.root, key, value);
+        self.root = root;
+        if old_value.is_none() {
+            self.len += 1;
+        }
+        old_value
+    }
+
+    fn insert_into(&mut self, index: u32, label: K, value: V) -> (u32, Option<V>) {
+        if index == NIL {
+            return (self.allocate(label, value), None);
+        }
+        let old_value = match label.cmp(&self.node(index).label) {
+            Ordering::Less => {
+                let (left, old_value) = self.insert_into(self.node(index).left, label, value);
+                self.node_mut(index).left = left;
+                old_value
+            }
+            Ordering::Greater => {
+                let (right, old_value) = self.insert_into(self.node(index).right, label, value);
+                self.node_mut(index).right = right;
+                old_value
+            }
+            Ordering::Equal => {
+                return (
+                    index,
+                    Some(mem::replace(&mut self.node_mut(index).value, value)),
+                )
+            }
+        };
+        (self.rebalance(index), old_value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (root, removed) = self.delete(self.root, key);
+        self.root = root;
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.root == NIL {
+            return None;
+        }
+        let (root, min) = self.detach_min(self.root);
+        self.root = root;
+        self.len -= 1;
+        let node = self.release(min);
+        Some((node.label, node.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.root == NIL {
+            return None;
+        }
+        let (root, max) = self.detach_max(self.root);
+        self.root = root;
+        self.len -= 1;
+        let node = self.release(max);
+        Some((node.label, node.value))
+    }
+
+    fn delete<Q>(&mut self, index: u32, target: &Q) -> (u32, Option<(K, V)>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if index == NIL {
+            return (NIL, None);
+        }
+        let removed = match target.cmp(self.node(index).label.borrow()) {
+            Ordering::Less => {
+                let (left, removed) = self.delete(self.node(index).left, target);
+                self.node_mut(index).left = left;
+                removed
+            }
+            Ordering::Greater => {
+                let (right, removed) = self.delete(self.node(index).right, target);
+                self.node_mut(index).right = right;
+                removed
+            }
+            Ordering::Equal => {
+                let (left, right) = (self.node(index).left, self.node(index).right);
+                // If either child is empty we promote the other one. If not,
+                // the smallest node on the right moves up into our place;
+                // only links change, the entries themselves stay put.
+                let replacement = if left == NIL {
+                    right
+                } else if right == NIL {
+                    left
+                } else {
+                    let (new_right, min) = self.detach_min(right);
+                    let moved = self.node_mut(min);
+                    moved.left = left;
+                    moved.right = new_right;
+                    self.rebalance(min)
+                };
+                let node = self.release(index);
+                return (replacement, Some((node.label, node.value)));
+            }
+        };
+        (self.rebalance(index), removed)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(&self.node(index).value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entry(self.find(key)?)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(&mut self.node_mut(index).value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Runs `f` on the value stored under `key`. Returns false, without
+    /// calling `f`, if `key` is missing. With no summaries to keep up to date
+    /// this is just get_mut, but it lets code written against `AvlMap` work
+    /// here unchanged.
+    pub fn update<Q, F>(&mut self, key: &Q, f: F) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        F: FnOnce(&mut V),
+    {
+        match self.get_mut(key) {
+            Some(value) => {
+                f(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<u32>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self.root;
+        while current != NIL {
+            let node = self.node(current);
+            current = match key.cmp(node.label.borrow()) {
+                Ordering::Less => node.left,
+                Ordering::Equal => return Some(current),
+                Ordering::Greater => node.right,
+            };
+        }
+        None
+    }
+
+    /// Builds a map from `left`, the pivot entry and `right`. Panics unless
+    /// every key in `left` is less than `key` and every key in `right` is
+    /// greater. The smaller map's nodes have to move into the bigger one's
+    /// slots, so this is O(log n) plus the size of the smaller map.
+    pub fn join(left: Self, key: K, value: V, right: Self) -> Self {
+        if let Some((left_last, _)) = left.last() {
+            assert!(*left_last < key, "join: left keys must be below the pivot");
+        }
+        if let Some((right_first, _)) = right.first() {
+            assert!(
+                *right_first > key,
+                "join: right keys must be above the pivot"
+            );
+        }
+        let left_is_bigger = left.len >= right.len;
+        let (mut base, mut other) = if left_is_bigger {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        let other_root = other.root;
+        let adopted = base.adopt(&mut other, other_root);
+        let pivot = base.allocate(key, value);
+        base.root = if left_is_bigger {
+            base.join_nodes(base.root, pivot, adopted)
+        } else {
+            base.join_nodes(adopted, pivot, base.root)
+        };
+        base.len += other.len + 1;
+        base
+    }
+
+    /// Splits the map in two at `key`: afterwards `self` holds the keys less
+    /// than `key` and the returned map holds the rest. The split is O(log n),
+    /// but the smaller half then has to move into slots of its own, which
+    /// costs O(size of that half).
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (below, found, above) = self.split(self.root, key);
+        let above = match found {
+            Some(pivot) => self.join_nodes(NIL, pivot, above),
+            None => above,
+        };
+        let mut other = ArenaMap::new();
+        if self.size_of(above) <= self.size_of(below) {
+            other.root = other.adopt(self, above);
+            self.root = below;
+        } else {
+            // The bottom half is the one that moves, so it ends up in the new
+            // slots and we swap to hand back the old ones.
+            other.root = other.adopt(self, below);
+            self.root = above;
+            mem::swap(self, &mut other);
+        }
+        self.len = self.size_of(self.root);
+        other.len = other.size_of(other.root);
+        other
+    }
+
+    // Cuts the subtree into the nodes below target, the node at target if
+    // there is one, and the nodes above target, all still in our slots.
+    fn split<Q>(&mut self, index: u32, target: &Q) -> (u32, Option<u32>, u32)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if index == NIL {
+            return (NIL, None, NIL);
+        }
+        let (left, right) = (self.node(index).left, self.node(index).right);
+        match target.cmp(self.node(index).label.borrow()) {
+            Ordering::Less => {
+                let (below, found, above) = self.split(left, target);
+                (below, found, self.join_nodes(above, index, right))
+            }
+            Ordering::Equal => (left, Some(index), right),
+            Ordering::Greater => {
+                let (below, found, above) = self.split(right, target);
+                (self.join_nodes(left, index, below), found, above)
+            }
+        }
+    }
+
+    /// Moves every entry of `other` into `self`, leaving `other` empty. When a
+    /// key is in both, the value from `other` wins. If the two key ranges do
+    /// not overlap this is a join, O(log n) plus the size of the smaller map;
+    /// otherwise the entries of `other` go in one at a time.
+    pub fn append(&mut self, other: &mut Self) {
+        let self_below_other = match (self.last(), other.first()) {
+            (Some((last, _)), Some((first, _))) => last < first,
+            _ => true,
+        };
+        let other_below_self = match (other.last(), self.first()) {
+            (Some((last, _)), Some((first, _))) => last < first,
+            _ => true,
+        };
+        if !self_below_other && !other_below_self {
+            for (key, value) in mem::take(other) {
+                self.insert(key, value);
+            }
+            return;
+        }
+        let mut other_is_above = self_below_other;
+        if self.len < other.len {
+            mem::swap(self, other);
+            other_is_above = !other_is_above;
+        }
+        let other_root = other.root;
+        let adopted = self.adopt(other, other_root);
+        self.root = if other_is_above {
+            self.join_without_pivot(self.root, adopted)
+        } else {
+            self.join_without_pivot(adopted, self.root)
+        };
+        self.len += other.len;
+        other.clear();
+    }
+}
+
+impl<K, V> Default for ArenaMap<K, V> {
+    fn default() -> Self {
+        ArenaMap::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for ArenaMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = ArenaMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for ArenaMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> IntoIterator for ArenaMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        let root = if self.root == NIL {
+            None
+        } else {
+            Some(self.root)
+        };
+        IntoIter {
+            walk: Walk::new(root),
+            slots: self.slots,
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a ArenaMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut ArenaMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+/// Borrowing iterator over the entries of an `ArenaMap`, in key order.
+pub struct Iter<'a, K, V> {
+    walk: Walk<Cursor<'a, K, V>, (&'a K, &'a V)>,
+    remaining: usize,
+}
+
+walk_iterator!(Iter<'a, K, V>, (&'a K, &'a V), split_ref);
+
+/// Iterator over the entries of an `ArenaMap` that can change the values.
+pub struct IterMut<'a, K, V> {
+    entries: vec::IntoIter<(&'a K, &'a mut V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// Owning iterator over the entries of an `ArenaMap`, in key order.
+pub struct IntoIter<K, V> {
+    slots: Vec<Slot<K, V>>,
+    walk: Walk<u32, (K, V)>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slots = &mut self.slots;
+        let entry = self.walk.next(|index| split_owned(slots, index))?;
+        self.remaining -= 1;
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let slots = &mut self.slots;
+        let entry = self.walk.next_back(|index| split_owned(slots, index))?;
+        self.remaining -= 1;
+        Some(entry)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+/// Borrowing iterator over the entries of an `ArenaMap` whose keys fall in a
+/// range, in key order.
+pub struct Range<'a, K, V> {
+    walk: Walk<Cursor<'a, K, V>, (&'a K, &'a V)>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.next(split_ref)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.walk.next_back(split_ref)
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+/// Iterator over the keys of an `ArenaMap`, in order.
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+/// Iterator over the values of an `ArenaMap`, in key order.
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+/// Iterator over the values of an `ArenaMap` that can change them.
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+projected_iterator!(Keys<'a, K, V>, &'a K, |(key, _)| key);
+projected_iterator!(Values<'a, K, V>, &'a V, |(_, value)| value);
+projected_iterator!(ValuesMut<'a, K, V>, &'a mut V, |(_, value)| value);
+
+// The walk only ever sees one subtree at a time, so each pending subtree
+// carries the whole arena along with its index.
+type Cursor<'a, K, V> = (&'a [Slot<K, V>], u32);
+
+fn split_ref<K, V>((slots, index): Cursor<'_, K, V>) -> Parts<Cursor<'_, K, V>, (&K, &V)> {
+    let node = match &slots[index as usize] {
+        Slot::Used(node) => node,
+        Slot::Free(_) => panic!("Slot {} is free but something still links to it.", index),
+    };
+    let child = |index: u32| {
+        if index == NIL {
+            None
+        } else {
+            Some((slots, index))
+        }
+    };
+    (
+        child(node.left),
+        (&node.label, &node.value),
+        child(node.right),
+    )
+}
+
+// The owning walk can't hold on to the slots itself, so it only carries
+// indices and we move each node out of its slot as the walk reaches it. The
+// walk splits every node exactly once, so the free slot we leave behind is
+// never looked at again.
+fn split_owned<K, V>(slots: &mut [Slot<K, V>], index: u32) -> Parts<u32, (K, V)> {
+    let node = match mem::replace(&mut slots[index as usize], Slot::Free(NIL)) {
+        Slot::Used(node) => node,
+        Slot::Free(_) => panic!("Slot {} is free but something still links to it.", index),
+    };
+    let child = |index: u32| if index == NIL { None } else { Some(index) };
+    (
+        child(node.left),
+        (node.label, node.value),
+        child(node.right),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AvlMap;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map: ArenaMap<&str, i64> = ArenaMap::new();
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("c", 3), None);
+        assert_eq!(map.insert("a", 10), Some(1));
+        *map.get_mut("c").unwrap() += 30;
+        assert_eq!(map.get("c"), Some(&33));
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&"a", &10), (&"c", &33)]
+        );
+        assert_eq!(map.first(), Some((&"a", &10)));
+        assert_eq!(map.last(), Some((&"c", &33)));
+        map.validate();
+    }
+
+    #[test]
+    fn test_freed_slots_are_reused() {
+        let mut map: ArenaMap<i64, i64> = (0..100).map(|n| (n, n)).collect();
+        for n in 0..50 {
+            map.remove(&(n * 2));
+        }
+        map.validate();
+        for n in 200..250 {
+            map.insert(n, n);
+        }
+        map.validate();
+        assert_eq!(map.slots.len(), 100);
+        assert_eq!(map.len(), 100);
+    }
+
+    #[test]
+    fn test_matches_btreemap() {
+        let mut map: ArenaMap<i64, i64> = ArenaMap::new();
+        let mut model: BTreeMap<i64, i64> = BTreeMap::new();
+        for n in 0..2000 {
+            let key = (n * 7919) % 251;
+            if n % 3 == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, n), model.insert(key, n));
+            }
+            assert_eq!(map.get(&(n % 251)), model.get(&(n % 251)));
+        }
+        map.validate();
+        assert_eq!(map.len(), model.len());
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.iter().rev().eq(model.iter().rev()));
+    }
+
+    #[test]
+    fn test_ordered_queries() {
+        let map: ArenaMap<i64, i64> = (0..100).rev().map(|n| (n * 10, n)).collect();
+        let keys = |range: Range<i64, i64>| range.map(|(key, _)| *key).collect::<Vec<i64>>();
+        assert_eq!(keys(map.range(100..130)), vec![100, 110, 120]);
+        assert_eq!(keys(map.range(95..=120)), vec![100, 110, 120]);
+        assert_eq!(keys(map.range(975..)), vec![980, 990]);
+        assert_eq!(keys(map.range(11..11)), vec![]);
+        assert_eq!(map.range(..).next_back(), Some((&990, &99)));
+        assert_eq!(map.floor(&25), Some((&20, &2)));
+        assert_eq!(map.floor(&-1), None);
+        assert_eq!(map.ceiling(&25), Some((&30, &3)));
+        assert_eq!(map.ceiling(&991), None);
+        assert_eq!(map.get_key_value(&40), Some((&40, &4)));
+        for n in 0..100 {
+            assert_eq!(map.select(n as usize), Some((&(n * 10), &n)));
+            assert_eq!(map.rank(&(n * 10)), n as usize);
+            assert_eq!(map.rank(&(n * 10 + 5)), n as usize + 1);
+        }
+        assert_eq!(map.select(100), None);
+        map.validate();
+    }
+
+    #[test]
+    fn test_pop_and_change_values() {
+        let mut map: ArenaMap<i64, i64> = (0..20).map(|n| (n, n)).collect();
+        assert_eq!(map.pop_first(), Some((0, 0)));
+        assert_eq!(map.pop_last(), Some((19, 19)));
+        for (_, value) in map.iter_mut() {
+            *value *= 10;
+        }
+        for value in map.values_mut().rev().take(3) {
+            *value += 1;
+        }
+        assert!(map.update(&5, |value| *value = -5));
+        assert!(!map.update(&0, |value| *value = -5));
+        map.validate();
+        assert_eq!(
+            map.keys().copied().collect::<Vec<i64>>(),
+            (1..19).collect::<Vec<i64>>()
+        );
+        assert_eq!(
+            map.values().take(5).copied().collect::<Vec<i64>>(),
+            vec![10, 20, 30, 40, -5]
+        );
+        let owned: Vec<(i64, i64)> = map.into_iter().rev().take(4).collect();
+        assert_eq!(owned, vec![(18, 181), (17, 171), (16, 161), (15, 150)]);
+        let mut empty: ArenaMap<i64, i64> = ArenaMap::new();
+        assert_eq!(empty.pop_first(), None);
+        assert_eq!(empty.pop_last(), None);
+    }
+
+    #[test]
+    fn test_into_iter_from_both_ends() {
+        let mut map: ArenaMap<i64, String> = (0..50).map(|n| (n, n.to_string())).collect();
+        // Leave some free slots in the arena for the walk to step around.
+        for key in (0..50).step_by(3) {
+            map.remove(&key);
+        }
+        let expected: Vec<i64> = (0..50).filter(|n| n % 3 != 0).collect();
+        let mut iter = map.into_iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while let Some((key, value)) = iter.next() {
+            assert_eq!(value, key.to_string());
+            front.push(key);
+            if let Some((key, _)) = iter.next_back() {
+                back.push(key);
+            }
+            assert_eq!(iter.len(), expected.len() - front.len() - back.len());
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, expected);
+        // Dropping one partway through has to drop the rest of the nodes too.
+        let map: ArenaMap<i64, String> = (0..50).map(|n| (n, n.to_string())).collect();
+        let mut iter = map.into_iter();
+        assert_eq!(iter.nth(10), Some((10, "10".to_string())));
+        assert_eq!(iter.next_back(), Some((49, "49".to_string())));
+    }
+
+    #[test]
+    fn test_split_join_and_append() {
+        for at in [0, 30, 250, 299, 1000] {
+            let mut map: ArenaMap<i64, i64> = (0..300).map(|n| (n, n)).collect();
+            let upper = map.split_off(&at);
+            map.validate();
+            upper.validate();
+            let at = at.min(300);
+            assert!(map.keys().copied().eq(0..at));
+            assert!(upper.keys().copied().eq(at..300));
+
+            let joined = match upper.first() {
+                Some((&first, _)) if first == at => {
+                    let mut upper = upper;
+                    let (key, value) = upper.pop_first().unwrap();
+                    ArenaMap::join(map, key, value, upper)
+                }
+                _ => {
+                    let mut map = map;
+                    let mut upper = upper;
+                    map.append(&mut upper);
+                    assert!(upper.is_empty());
+                    map
+                }
+            };
+            joined.validate();
+            assert!(joined.keys().copied().eq(0..300));
+        }
+
+        let mut low: ArenaMap<i64, i64> = (0..10).map(|n| (n, n)).collect();
+        let mut high: ArenaMap<i64, i64> = (10..500).map(|n| (n, n)).collect();
+        high.append(&mut low);
+        high.validate();
+        assert!(low.is_empty());
+        assert!(high.keys().copied().eq(0..500));
+
+        let mut evens: ArenaMap<i64, i64> = (0..100).map(|n| (n * 2, 0)).collect();
+        let mut others: ArenaMap<i64, i64> = (50..150).map(|n| (n, 1)).collect();
+        evens.append(&mut others);
+        evens.validate();
+        assert_eq!(evens.len(), 50 + 100);
+        assert_eq!(evens.get(&60), Some(&1));
+    }
+
+    #[test]
+    #[should_panic(expected = "left keys must be below the pivot")]
+    fn test_join_rejects_overlap() {
+        let left: ArenaMap<i64, i64> = (0..10).map(|n| (n, n)).collect();
+        ArenaMap::join(left, 5, 5, ArenaMap::new());
+    }
+
+    #[test]
+    fn test_check_catches_stale_size() {
+        let mut map: ArenaMap<i64, i64> = (0..7).map(|n| (n, n)).collect();
+        assert_eq!(map.check(), Ok(()));
+        let root = map.root;
+        map.node_mut(root).size = 3;
+        assert_eq!(
+            map.check(),
+            Err(Violation::StaleSize {
+                position: 3,
+                stored: 3,
+                correct: 7,
+            })
+        );
+    }
+
+    // The point of matching AvlMap's API: the same code runs on both.
+    #[test]
+    fn test_same_answers_as_avl_map() {
+        let mut arena: ArenaMap<i64, i64> = ArenaMap::new();
+        let mut boxed: AvlMap<i64, i64> = AvlMap::new();
+        for n in 0..1000 {
+            let key = (n * 7919) % 331;
+            match n % 5 {
+                0 => assert_eq!(arena.remove(&key), boxed.remove(&key)),
+                1 => assert_eq!(arena.pop_first(), boxed.pop_first()),
+                _ => assert_eq!(arena.insert(key, n), boxed.insert(key, n)),
+            }
+            assert_eq!(arena.floor(&key), boxed.floor(&key));
+            assert_eq!(arena.rank(&key), boxed.rank(&key));
+            assert_eq!(arena.select(n as usize % 50), boxed.select(n as usize % 50));
+        }
+        assert!(arena.range(100..200).eq(boxed.range(100..200)));
+        let (arena_upper, boxed_upper) = (arena.split_off(&150), boxed.split_off(&150));
+        assert!(arena.iter().eq(boxed.iter()));
+        assert!(arena_upper.into_iter().eq(boxed_upper));
+    }
+}
@@ -0,0 +1,169 @@
+use std::borrow::Borrow;
+use std::ops::{Bound, RangeBounds};
+
+use crate::node::{get_summary, AVLTree};
+
+/// A summary that every node keeps for its whole subtree, such as a sum, a
+/// minimum or a maximum. The tree recomputes it whenever a subtree changes
+/// shape, so range aggregates only ever have to look at O(log n) nodes.
+///
+/// `combine` must be associative and `identity` must leave anything it is
+/// combined with unchanged. It does not have to be commutative: summaries are
+/// always combined in key order.
+pub trait Augment<K, V>: Clone {
+    fn identity() -> Self;
+
+    fn single(key: &K, value: &V) -> Self;
+
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// The default: keep no summary at all.
+impl<K, V> Augment<K, V> for () {
+    fn identity() {}
+
+    fn single(_key: &K, _value: &V) {}
+
+    fn combine(&self, _other: &()) {}
+}
+
+// The same shape as Range::new: find the highest node in range, then walk
+// down either side of it picking up whole subtrees that are entirely in range.
+pub(crate) fn aggregate<K, V, A, Q, R>(tree: &AVLTree<K, V, A>, range: &R) -> A
+where
+    K: Borrow<Q>,
+    A: Augment<K, V>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+{
+    let above_start = |label: &K| match range.start_bound() {
+        Bound::Included(start) => label.borrow() >= start,
+        Bound::Excluded(start) => label.borrow() > start,
+        Bound::Unbounded => true,
+    };
+    let below_end = |label: &K| match range.end_bound() {
+        Bound::Included(end) => label.borrow() <= end,
+        Bound::Excluded(end) => label.borrow() < end,
+        Bound::Unbounded => true,
+    };
+
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        if !above_start(&node.label) {
+            current = node.right.as_deref();
+        } else if !below_end(&node.label) {
+            current = node.left.as_deref();
+        } else {
+            break;
+        }
+    }
+    let top = match current {
+        Some(node) => node,
+        None => return A::identity(),
+    };
+
+    // What we find on the left comes before everything we have so far.
+    let mut from_left = A::identity();
+    let mut current = top.left.as_deref();
+    while let Some(node) = current {
+        if above_start(&node.label) {
+            from_left = A::single(&node.label, &node.value)
+                .combine(&get_summary(&node.right))
+                .combine(&from_left);
+            current = node.left.as_deref();
+        } else {
+            current = node.right.as_deref();
+        }
+    }
+    // And what we find on the right comes after.
+    let mut from_right = A::identity();
+    let mut current = top.right.as_deref();
+    while let Some(node) = current {
+        if below_end(&node.label) {
+            from_right = from_right
+                .combine(&get_summary(&node.left))
+                .combine(&A::single(&node.label, &node.value));
+            current = node.right.as_deref();
+        } else {
+            current = node.left.as_deref();
+        }
+    }
+    from_left
+        .combine(&A::single(&top.label, &top.value))
+        .combine(&from_right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AvlMap;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sum(i64);
+
+    impl<K> Augment<K, i64> for Sum {
+        fn identity() -> Self {
+            Sum(0)
+        }
+
+        fn single(_key: &K, value: &i64) -> Self {
+            Sum(*value)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    // Keeps the keys in order, so it notices if we ever combine out of order.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Concat(String);
+
+    impl Augment<char, ()> for Concat {
+        fn identity() -> Self {
+            Concat(String::new())
+        }
+
+        fn single(key: &char, _value: &()) -> Self {
+            Concat(key.to_string())
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Concat(format!("{}{}", self.0, other.0))
+        }
+    }
+
+    #[test]
+    fn test_sum_through_inserts_and_removes() {
+        let mut map: AvlMap<i64, i64, Sum> = AvlMap::default();
+        for n in 0..100 {
+            map.insert(n, n);
+        }
+        assert_eq!(map.summary(), Sum(4950));
+        assert_eq!(map.aggregate(10..20), Sum(145));
+        assert_eq!(map.aggregate(..=9), Sum(45));
+        assert_eq!(map.aggregate(90..), Sum(945));
+        assert_eq!(map.aggregate(200..300), Sum(0));
+        for n in 0..50 {
+            map.remove(&(n * 2));
+        }
+        assert_eq!(map.summary(), Sum(2500));
+        map.insert(1, 1000);
+        assert!(map.update(&3, |value| *value = 0));
+        assert!(!map.update(&4, |value| *value = 0));
+        assert_eq!(map.aggregate(0..5), Sum(1000));
+        map.validate();
+    }
+
+    #[test]
+    fn test_summary_survives_split_and_join() {
+        let mut map: AvlMap<char, (), Concat> = ('a'..='z').map(|c| (c, ())).collect();
+        assert_eq!(map.summary().0, "abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(map.aggregate('h'..'p').0, "hijklmno");
+        let upper = map.split_off(&'m');
+        assert_eq!(map.summary().0, "abcdefghijkl");
+        assert_eq!(upper.summary().0, "mnopqrstuvwxyz");
+        let rejoined = map.union(upper);
+        assert_eq!(rejoined.aggregate(..).0, "abcdefghijklmnopqrstuvwxyz");
+    }
+}
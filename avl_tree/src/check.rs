@@ -0,0 +1,444 @@
+// Walks the whole tree making sure the labels really are in order, every node
+// is in balance, and every cached field (height, size, summary) matches what
+// is underneath it. It reports the first problem it finds instead of
+// panicking, so tests can say what they expected to go wrong. validate just
+// panics with whatever this finds.
+
+use std::cmp::max;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::augment::Augment;
+use crate::node::{AVLNode, LinkKind};
+
+/// The first thing `AvlMap::check` or `ArenaMap::check` found wrong with a
+/// tree. Nodes are named by their position in key order, counting from zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// This node's key is not between the keys of its ancestors.
+    OutOfOrder {
+        position: usize,
+    },
+    /// This node's subtrees differ in height by more than one.
+    Unbalanced {
+        position: usize,
+        balance_factor: i64,
+    },
+    StaleHeight {
+        position: usize,
+        stored: i64,
+        correct: i64,
+    },
+    StaleSize {
+        position: usize,
+        stored: usize,
+        correct: usize,
+    },
+    StaleSummary {
+        position: usize,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::OutOfOrder { position } => {
+                write!(f, "node {} is out of order", position)
+            }
+            Violation::Unbalanced {
+                position,
+                balance_factor,
+            } => write!(
+                f,
+                "node {} has a balance factor of {}",
+                position, balance_factor
+            ),
+            Violation::StaleHeight {
+                position,
+                stored,
+                correct,
+            } => write!(
+                f,
+                "node {} says its height is {} but it is {}",
+                position, stored, correct
+            ),
+            Violation::StaleSize {
+                position,
+                stored,
+                correct,
+            } => write!(
+                f,
+                "node {} says its size is {} but it is {}",
+                position, stored, correct
+            ),
+            Violation::StaleSummary { position } => {
+                write!(f, "node {} has a stale summary", position)
+            }
+        }
+    }
+}
+
+impl Error for Violation {}
+
+pub(crate) fn check<K, V, A, L, P>(tree: &Option<P>) -> Result<(), Violation>
+where
+    K: Ord,
+    A: Augment<K, V> + PartialEq,
+    L: LinkKind<K, V, A>,
+    P: Deref<Target = AVLNode<K, V, A, L>>,
+{
+    check_subtree(tree.as_deref(), 0, None, None).map(|_| ())
+}
+
+// Returns the correct height, size and summary of the subtree. offset is how
+// many nodes come before it in key order, and every label in it has to be
+// strictly between lower and upper.
+fn check_subtree<'a, K, V, A, L>(
+    tree: Option<&'a AVLNode<K, V, A, L>>,
+    offset: usize,
+    lower: Option<&'a K>,
+    upper: Option<&'a K>,
+) -> Result<(i64, usize, A), Violation>
+where
+    K: Ord,
+    A: Augment<K, V> + PartialEq,
+    L: LinkKind<K, V, A>,
+{
+    let node = match tree {
+        Some(node) => node,
+        None => return Ok((0, 0, A::identity())),
+    };
+    let (left_height, left_size, left_summary) =
+        check_subtree(node.left.as_deref(), offset, lower, Some(&node.label))?;
+    let position = offset + left_size;
+    let above_lower = lower.is_none_or(|lower| *lower < node.label);
+    let below_upper = upper.is_none_or(|upper| node.label < *upper);
+    if !above_lower || !below_upper {
+        return Err(Violation::OutOfOrder { position });
+    }
+    let (right_height, right_size, right_summary) = check_subtree(
+        node.right.as_deref(),
+        position + 1,
+        Some(&node.label),
+        upper,
+    )?;
+
+    let balance_factor = left_height - right_height;
+    if !(-1..=1).contains(&balance_factor) {
+        return Err(Violation::Unbalanced {
+            position,
+            balance_factor,
+        });
+    }
+    let height = 1 + max(left_height, right_height);
+    if height != node.height {
+        return Err(Violation::StaleHeight {
+            position,
+            stored: node.height,
+            correct: height,
+        });
+    }
+    let size = 1 + left_size + right_size;
+    if size != node.size {
+        return Err(Violation::StaleSize {
+            position,
+            stored: node.size,
+            correct: size,
+        });
+    }
+    let summary = left_summary
+        .combine(&A::single(&node.label, &node.value))
+        .combine(&right_summary);
+    if summary != node.summary {
+        return Err(Violation::StaleSummary { position });
+    }
+    Ok((height, size, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::AvlMap;
+    use crate::node::build_balanced;
+    use std::collections::BTreeMap;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn test_check_catches_corruption() {
+        let fresh = || build_balanced::<i64, (), ()>(7, &mut (0..7).map(|n| (n, ())));
+        assert_eq!(check(&fresh()), Ok(()));
+
+        // 0..7 builds a perfect tree: 3 at the root, 1 and 5 below it.
+        let mut tree = fresh();
+        tree.as_mut().unwrap().left.as_mut().unwrap().label = 10;
+        assert_eq!(check(&tree), Err(Violation::OutOfOrder { position: 1 }));
+
+        let mut tree = fresh();
+        tree.as_mut().unwrap().size = 6;
+        assert_eq!(
+            check(&tree),
+            Err(Violation::StaleSize {
+                position: 3,
+                stored: 6,
+                correct: 7
+            })
+        );
+
+        let mut tree = fresh();
+        tree.as_mut().unwrap().right.as_mut().unwrap().height = 5;
+        assert_eq!(
+            check(&tree),
+            Err(Violation::StaleHeight {
+                position: 5,
+                stored: 5,
+                correct: 2
+            })
+        );
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Sum(i64);
+
+    impl Augment<i64, i64> for Sum {
+        fn identity() -> Self {
+            Sum(0)
+        }
+
+        fn single(_key: &i64, value: &i64) -> Self {
+            Sum(*value)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            Sum(self.0 + other.0)
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Op {
+        Insert(i64, i64),
+        Remove(i64),
+        Get(i64),
+        Range(i64, i64),
+        Rank(i64),
+        Select(usize),
+        PopFirst,
+        PopLast,
+        // Split off everything from here up, check both halves, and put them
+        // back together.
+        SplitAppend(i64),
+    }
+
+    impl Op {
+        // Versions of this op with smaller numbers in it, for shrinking.
+        fn simpler(&self) -> Vec<Op> {
+            let smaller = |n: i64| {
+                let mut found: Vec<i64> = vec![0, n / 2, n - n.signum()];
+                found.retain(|m| m.abs() < n.abs());
+                found.dedup();
+                found
+            };
+            match self {
+                Op::Insert(key, value) => {
+                    let mut found: Vec<Op> = smaller(*key)
+                        .into_iter()
+                        .map(|key| Op::Insert(key, *value))
+                        .collect();
+                    found.extend(
+                        smaller(*value)
+                            .into_iter()
+                            .map(|value| Op::Insert(*key, value)),
+                    );
+                    found
+                }
+                Op::Remove(key) => smaller(*key).into_iter().map(Op::Remove).collect(),
+                Op::Get(key) => smaller(*key).into_iter().map(Op::Get).collect(),
+                Op::Rank(key) => smaller(*key).into_iter().map(Op::Rank).collect(),
+                Op::SplitAppend(key) => smaller(*key).into_iter().map(Op::SplitAppend).collect(),
+                Op::Range(low, high) => smaller(*low)
+                    .into_iter()
+                    .map(|low| Op::Range(low, *high))
+                    .chain(smaller(*high).into_iter().map(|high| Op::Range(*low, high)))
+                    .collect(),
+                Op::Select(index) => smaller(*index as i64)
+                    .into_iter()
+                    .map(|index| Op::Select(index as usize))
+                    .collect(),
+                Op::PopFirst | Op::PopLast => vec![],
+            }
+        }
+    }
+
+    // xorshift64, which is plenty random enough to pick operations with.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> i64 {
+            (self.next() % n) as i64
+        }
+    }
+
+    fn random_ops(rng: &mut Rng, len: usize) -> Vec<Op> {
+        // A small key space, so that removes and lookups often hit.
+        let key = |rng: &mut Rng| rng.below(64);
+        (0..len)
+            .map(|_| match rng.below(20) {
+                0..=7 => Op::Insert(key(rng), rng.below(1000)),
+                8..=11 => Op::Remove(key(rng)),
+                12 => Op::Get(key(rng)),
+                13 => Op::Range(key(rng), key(rng)),
+                14 => Op::Rank(key(rng)),
+                15 => Op::Select(rng.below(70) as usize),
+                16 => Op::PopFirst,
+                17 => Op::PopLast,
+                _ => Op::SplitAppend(key(rng)),
+            })
+            .collect()
+    }
+
+    // Plays ops against both an AvlMap and a BTreeMap, and says where they
+    // first disagree.
+    fn replay(ops: &[Op]) -> Result<(), String> {
+        let mut map: AvlMap<i64, i64, Sum> = AvlMap::default();
+        let mut model: BTreeMap<i64, i64> = BTreeMap::new();
+        for (step, op) in ops.iter().enumerate() {
+            let agreed = match op {
+                Op::Insert(key, value) => map.insert(*key, *value) == model.insert(*key, *value),
+                Op::Remove(key) => map.remove(key) == model.remove(key),
+                Op::Get(key) => map.get(key) == model.get(key),
+                Op::Range(low, high) => {
+                    let found: Vec<(&i64, &i64)> = map.range(low..=high).collect();
+                    let expected: Vec<(&i64, &i64)> = if low <= high {
+                        model.range(low..=high).collect()
+                    } else {
+                        vec![]
+                    };
+                    let expected_sum = expected.iter().map(|(_, value)| **value).sum();
+                    found == expected && map.aggregate(low..=high) == Sum(expected_sum)
+                }
+                Op::Rank(key) => map.rank(key) == model.range(..key).count(),
+                Op::Select(index) => map.select(*index) == model.iter().nth(*index),
+                Op::PopFirst => map.pop_first() == model.pop_first(),
+                Op::PopLast => map.pop_last() == model.pop_last(),
+                Op::SplitAppend(key) => {
+                    let mut upper = map.split_off(key);
+                    let model_upper = model.split_off(key);
+                    let halves_ok = map.check().is_ok()
+                        && upper.check().is_ok()
+                        && upper.iter().eq(model_upper.iter());
+                    map.append(&mut upper);
+                    model.extend(model_upper);
+                    halves_ok && upper.is_empty()
+                }
+            };
+            if !agreed {
+                return Err(format!("step {}: {:?} disagreed with BTreeMap", step, op));
+            }
+            if let Err(violation) = map.check() {
+                return Err(format!("step {}: after {:?}, {}", step, op, violation));
+            }
+            if map.len() != model.len() || map.summary() != Sum(model.values().sum()) {
+                return Err(format!("step {}: after {:?}, len or sum is off", step, op));
+            }
+        }
+        if map.iter().eq(model.iter()) {
+            Ok(())
+        } else {
+            Err("the final contents disagree".to_string())
+        }
+    }
+
+    fn fails(ops: &[Op]) -> bool {
+        // A panic inside the tree counts as a failure too.
+        panic::catch_unwind(AssertUnwindSafe(|| replay(ops))).map_or(true, |result| result.is_err())
+    }
+
+    // Cuts a failing sequence down while it keeps failing: first by dropping
+    // ever smaller chunks of ops, then by making the numbers in what is left
+    // smaller.
+    fn shrink(ops: Vec<Op>, fails: impl Fn(&[Op]) -> bool) -> Vec<Op> {
+        let mut ops = ops;
+        let mut chunk = ops.len() / 2;
+        while chunk > 0 {
+            let mut dropped_any = false;
+            let mut start = 0;
+            while start < ops.len() {
+                let end = (start + chunk).min(ops.len());
+                let candidate: Vec<Op> = [&ops[..start], &ops[end..]].concat();
+                if fails(&candidate) {
+                    ops = candidate;
+                    dropped_any = true;
+                } else {
+                    start += chunk;
+                }
+            }
+            if !dropped_any {
+                chunk /= 2;
+            }
+        }
+        let mut simplified = true;
+        while simplified {
+            simplified = false;
+            for i in 0..ops.len() {
+                for simpler in ops[i].simpler() {
+                    let mut candidate = ops.clone();
+                    candidate[i] = simpler;
+                    if fails(&candidate) {
+                        ops = candidate;
+                        simplified = true;
+                        break;
+                    }
+                }
+            }
+        }
+        ops
+    }
+
+    #[test]
+    fn test_matches_btreemap() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            let ops = random_ops(&mut rng, 300);
+            if fails(&ops) {
+                let minimal = shrink(ops, fails);
+                panic!("{:?} fails: {}", minimal, replay(&minimal).unwrap_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_finds_the_guilty_pair() {
+        // Pretend the bug is removing a key of 10 or more that was inserted
+        // earlier, and bury one of those in noise.
+        let buggy = |ops: &[Op]| {
+            ops.iter().enumerate().any(|(i, op)| match op {
+                Op::Insert(key, _) if *key >= 10 => ops[i..].contains(&Op::Remove(*key)),
+                _ => false,
+            })
+        };
+        let mut rng = Rng(7);
+        let ops: Vec<Op> = random_ops(&mut rng, 200)
+            .into_iter()
+            .filter(|op| !matches!(op, Op::Remove(_)))
+            .chain([Op::Insert(37, 5)])
+            .chain(random_ops(&mut rng, 200))
+            .collect();
+        assert!(buggy(&ops));
+        let minimal = shrink(ops, buggy);
+        assert_eq!(minimal.len(), 2);
+        match (&minimal[0], &minimal[1]) {
+            (Op::Insert(inserted, 0), Op::Remove(removed)) => {
+                assert_eq!(inserted, removed);
+                assert!(*inserted >= 10);
+            }
+            other => panic!("shrank to {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt::Debug;
+use std::iter::FusedIterator;
+use std::ops::{Bound, Range};
+
+use crate::augment::Augment;
+use crate::map::{self, AvlMap};
+use crate::node::AVLNode;
+
+/// A half-open interval `[start, end)`. Intervals are ordered by start, then
+/// by end, which is the order an `IntervalTree` keeps them in.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<K> {
+    pub start: K,
+    pub end: K,
+}
+
+impl<K> From<Range<K>> for Interval<K> {
+    fn from(range: Range<K>) -> Self {
+        Interval {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+// The largest end anywhere in a subtree. If that is not past the start of
+// what we are looking for, nothing in the subtree can overlap it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaxEnd<K>(Option<K>);
+
+impl<K: Clone + Ord, V> Augment<Interval<K>, V> for MaxEnd<K> {
+    fn identity() -> Self {
+        MaxEnd(None)
+    }
+
+    fn single(key: &Interval<K>, _value: &V) -> Self {
+        MaxEnd(Some(key.end.clone()))
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        MaxEnd(match (&self.0, &other.0) {
+            (Some(mine), Some(theirs)) => Some(mine.max(theirs).clone()),
+            (Some(only), None) | (None, Some(only)) => Some(only.clone()),
+            (None, None) => None,
+        })
+    }
+}
+
+type IntervalNode<K, V> = AVLNode<Interval<K>, V, MaxEnd<K>>;
+
+/// A map from half-open intervals to values that can answer "what overlaps
+/// this?" in O(log n + k) for k answers.
+#[derive(Debug)]
+pub struct IntervalTree<K, V> {
+    map: AvlMap<Interval<K>, V, MaxEnd<K>>,
+}
+
+impl<K, V> IntervalTree<K, V> {
+    pub fn new() -> Self {
+        IntervalTree {
+            map: AvlMap::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Every interval and its value, ordered by start and then end.
+    pub fn iter(&self) -> map::Iter<'_, Interval<K>, V, MaxEnd<K>> {
+        self.map.iter()
+    }
+}
+
+impl<K: Clone + Ord, V> IntervalTree<K, V> {
+    /// Returns the value previously stored under exactly the same interval.
+    /// Panics if the interval is empty, like `5..5` or `9..3`: no query
+    /// could ever find it, and a backwards one would throw off the max ends.
+    pub fn insert(&mut self, interval: Range<K>, value: V) -> Option<V> {
+        assert!(
+            !interval.is_empty(),
+            "insert: the interval must not be empty"
+        );
+        self.map.insert(interval.into(), value)
+    }
+
+    pub fn remove(&mut self, interval: Range<K>) -> Option<V> {
+        self.map.remove(&Interval::from(interval))
+    }
+
+    pub fn get(&self, interval: Range<K>) -> Option<&V> {
+        self.map.get(&Interval::from(interval))
+    }
+
+    /// Every stored interval that shares at least one point with `query`, in
+    /// order. An empty query overlaps nothing.
+    pub fn overlapping(&self, query: Range<K>) -> Overlapping<'_, K, V> {
+        let root = if query.is_empty() {
+            None
+        } else {
+            self.map.root().as_deref()
+        };
+        Overlapping::new(root, query.start, Bound::Excluded(query.end))
+    }
+
+    /// Every stored interval that contains `point`, in order.
+    pub fn stab(&self, point: K) -> Overlapping<'_, K, V> {
+        Overlapping::new(
+            self.map.root().as_deref(),
+            point.clone(),
+            Bound::Included(point),
+        )
+    }
+}
+
+impl<K: Clone + Ord + Debug, V> IntervalTree<K, V> {
+    /// Panics if the tree is out of balance or a subtree's max end is stale.
+    pub fn validate(&self) {
+        self.map.validate();
+        check_max_end(self.map.root().as_deref());
+    }
+}
+
+fn check_max_end<K: Clone + Ord + Debug, V>(subtree: Option<&IntervalNode<K, V>>) -> Option<K> {
+    let node = subtree?;
+    let correct = [
+        check_max_end(node.left.as_deref()),
+        Some(node.label.end.clone()),
+        check_max_end(node.right.as_deref()),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    if correct != node.summary.0 {
+        panic!(
+            "My max end should say {:?} but instead it says {:?}",
+            correct, node.summary.0
+        );
+    }
+    correct
+}
+
+impl<K, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        IntervalTree::new()
+    }
+}
+
+/// Iterator over the intervals that overlap a query, in order.
+pub struct Overlapping<'a, K, V> {
+    // Nodes whose own interval and right subtree are still to be looked at,
+    // deepest last, the same as an ordinary in-order walk.
+    stack: Vec<&'a IntervalNode<K, V>>,
+    // We want intervals that end after low and start before (or at) high.
+    low: K,
+    high: Bound<K>,
+}
+
+impl<'a, K: Ord, V> Overlapping<'a, K, V> {
+    fn new(root: Option<&'a IntervalNode<K, V>>, low: K, high: Bound<K>) -> Self {
+        let mut overlapping = Overlapping {
+            stack: vec![],
+            low,
+            high,
+        };
+        overlapping.push_left_spine(root);
+        overlapping
+    }
+
+    fn starts_in_time(&self, start: &K) -> bool {
+        match &self.high {
+            Bound::Included(high) => start <= high,
+            Bound::Excluded(high) => start < high,
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn push_left_spine(&mut self, subtree: Option<&'a IntervalNode<K, V>>) {
+        let mut current = subtree;
+        while let Some(node) = current {
+            match &node.summary.0 {
+                Some(max_end) if *max_end > self.low => (),
+                // Everything down here ends too early.
+                _ => return,
+            }
+            // If this interval starts too late then so does everything to its
+            // right, but the left subtree may still have something.
+            if self.starts_in_time(&node.label.start) {
+                self.stack.push(node);
+            }
+            current = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Overlapping<'a, K, V> {
+    type Item = (&'a Interval<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            self.push_left_spine(node.right.as_deref());
+            if node.label.end > self.low {
+                return Some((&node.label, &node.value));
+            }
+        }
+        None
+    }
+}
+
+impl<K: Ord, V> FusedIterator for Overlapping<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(found: Overlapping<i64, char>) -> Vec<i64> {
+        found.map(|(interval, _)| interval.start).collect()
+    }
+
+    #[test]
+    fn test_overlapping_and_stab() {
+        let mut tree: IntervalTree<i64, char> = IntervalTree::new();
+        tree.insert(0..10, 'a');
+        tree.insert(5..6, 'b');
+        tree.insert(8..20, 'c');
+        tree.insert(12..15, 'd');
+        tree.insert(30..40, 'e');
+        tree.validate();
+
+        assert_eq!(starts(tree.overlapping(6..9)), vec![0, 8]);
+        assert_eq!(starts(tree.overlapping(9..13)), vec![0, 8, 12]);
+        assert_eq!(starts(tree.overlapping(20..30)), vec![]);
+        assert_eq!(starts(tree.overlapping(5..5)), vec![]);
+        assert_eq!(starts(tree.stab(5)), vec![0, 5]);
+        assert_eq!(starts(tree.stab(10)), vec![8]);
+        assert_eq!(starts(tree.stab(20)), vec![]);
+        assert_eq!(starts(tree.stab(39)), vec![30]);
+
+        assert_eq!(tree.remove(8..20), Some('c'));
+        assert_eq!(tree.remove(8..20), None);
+        tree.validate();
+        assert_eq!(starts(tree.overlapping(9..13)), vec![0, 12]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be empty")]
+    fn test_insert_rejects_empty() {
+        IntervalTree::new().insert(5..5, 'a');
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be empty")]
+    fn test_insert_rejects_backwards() {
+        let (start, end) = (9, 3);
+        IntervalTree::new().insert(start..end, 'a');
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut tree: IntervalTree<i64, usize> = IntervalTree::new();
+        let mut all: Vec<(i64, i64)> = vec![];
+        for n in 0..300 {
+            let start = (n * 7919) % 1000;
+            let end = start + 1 + (n * 104729) % 60;
+            if tree.insert(start..end, n as usize).is_none() {
+                all.push((start, end));
+            }
+        }
+        tree.validate();
+        all.sort();
+        for low in (0..1100).step_by(37) {
+            let high = low + (low % 50);
+            let expected: Vec<(i64, i64)> = all
+                .iter()
+                .filter(|(start, end)| *start < high && *end > low)
+                .copied()
+                .collect();
+            let found: Vec<(i64, i64)> = tree
+                .overlapping(low..high)
+                .map(|(interval, _)| (interval.start, interval.end))
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+
+use crate::node::AVLNode;
+
+// One piece of work left for an iterator: either a subtree we have not
+// looked inside yet, or an entry that is ready to hand out.
+enum Pending<S, E> {
+    Subtree(S),
+    Entry(E),
+}
+
+// A subtree taken apart into its left child, its own entry and its right child.
+pub(crate) type Parts<S, E> = (Option<S>, E, Option<S>);
+
+// The stack that drives every in-order iterator. Going forwards we pop from the
+// front and unfold subtrees down their left spines; going backwards we do the
+// mirror image at the back. Either end only ever holds one spine's worth of
+// pending work, so a walk costs O(height) memory rather than O(n). Both ends
+// share the one deque, so they can never hand out the same entry twice.
+//
+// The walk doesn't keep hold of how to take a subtree apart; each step is
+// handed a split instead. Most iterators pass a plain function, but the
+// arena's owning iterator needs one that borrows its slots to move nodes out.
+pub(crate) struct Walk<S, E> {
+    pending: VecDeque<Pending<S, E>>,
+}
+
+impl<S, E> Walk<S, E> {
+    pub(crate) fn new(root: Option<S>) -> Self {
+        let mut pending = VecDeque::new();
+        if let Some(subtree) = root {
+            pending.push_back(Pending::Subtree(subtree));
+        }
+        Walk { pending }
+    }
+
+    pub(crate) fn next(&mut self, mut split: impl FnMut(S) -> Parts<S, E>) -> Option<E> {
+        loop {
+            match self.pending.pop_front()? {
+                Pending::Entry(entry) => return Some(entry),
+                Pending::Subtree(subtree) => {
+                    let (left, entry, right) = split(subtree);
+                    if let Some(right) = right {
+                        self.pending.push_front(Pending::Subtree(right));
+                    }
+                    self.pending.push_front(Pending::Entry(entry));
+                    if let Some(left) = left {
+                        self.pending.push_front(Pending::Subtree(left));
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn next_back(&mut self, mut split: impl FnMut(S) -> Parts<S, E>) -> Option<E> {
+        loop {
+            match self.pending.pop_back()? {
+                Pending::Entry(entry) => return Some(entry),
+                Pending::Subtree(subtree) => {
+                    let (left, entry, right) = split(subtree);
+                    if let Some(left) = left {
+                        self.pending.push_back(Pending::Subtree(left));
+                    }
+                    self.pending.push_back(Pending::Entry(entry));
+                    if let Some(right) = right {
+                        self.pending.push_back(Pending::Subtree(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, S, K, V> Walk<S, (&'a K, &'a V)> {
+    // A walk over only the entries whose keys fall in range. Rather than
+    // starting from the root, we seed it with only the parts of the tree inside
+    // the range: the highest node in range, then the in-range slivers of its
+    // two subtrees. Every subtree we push is entirely in range, so from then on
+    // the walk needs no comparisons at all.
+    pub(crate) fn range<Q, R>(
+        root: Option<S>,
+        split: fn(S) -> Parts<S, (&'a K, &'a V)>,
+        range: &R,
+    ) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let above_start = |label: &K| match range.start_bound() {
+            Bound::Included(start) => label.borrow() >= start,
+            Bound::Excluded(start) => label.borrow() > start,
+            Bound::Unbounded => true,
+        };
+        let below_end = |label: &K| match range.end_bound() {
+            Bound::Included(end) => label.borrow() <= end,
+            Bound::Excluded(end) => label.borrow() < end,
+            Bound::Unbounded => true,
+        };
+
+        let mut walk = Walk::new(None);
+        let mut current = root;
+        let (top_left, top, top_right) = loop {
+            let (left, entry, right) = match current {
+                Some(subtree) => split(subtree),
+                None => return walk,
+            };
+            if !above_start(entry.0) {
+                current = right;
+            } else if !below_end(entry.0) {
+                current = left;
+            } else {
+                break (left, entry, right);
+            }
+        };
+        walk.pending.push_back(Pending::Entry(top));
+
+        // Everything left of top is below the end, so only the start matters.
+        let mut current = top_left;
+        while let Some(subtree) = current {
+            let (left, entry, right) = split(subtree);
+            if above_start(entry.0) {
+                if let Some(right) = right {
+                    walk.pending.push_front(Pending::Subtree(right));
+                }
+                walk.pending.push_front(Pending::Entry(entry));
+                current = left;
+            } else {
+                current = right;
+            }
+        }
+        // And everything right of top is above the start.
+        let mut current = top_right;
+        while let Some(subtree) = current {
+            let (left, entry, right) = split(subtree);
+            if below_end(entry.0) {
+                if let Some(left) = left {
+                    walk.pending.push_back(Pending::Subtree(left));
+                }
+                walk.pending.push_back(Pending::Entry(entry));
+                current = right;
+            } else {
+                current = left;
+            }
+        }
+        walk
+    }
+}
+
+fn split_ref<K, V, A>(node: &AVLNode<K, V, A>) -> Parts<&AVLNode<K, V, A>, (&K, &V)> {
+    (
+        node.left.as_deref(),
+        (&node.label, &node.value),
+        node.right.as_deref(),
+    )
+}
+
+fn split_mut<K, V, A>(node: &mut AVLNode<K, V, A>) -> Parts<&mut AVLNode<K, V, A>, (&K, &mut V)> {
+    let AVLNode {
+        label,
+        value,
+        left,
+        right,
+        ..
+    } = node;
+    (left.as_deref_mut(), (&*label, value), right.as_deref_mut())
+}
+
+fn split_owned<K, V, A>(node: AVLNode<K, V, A>) -> Parts<AVLNode<K, V, A>, (K, V)> {
+    (
+        node.left.map(|child| *child),
+        (node.label, node.value),
+        node.right.map(|child| *child),
+    )
+}
+
+/// Borrowing iterator over the entries of an `AvlMap`, in key order.
+pub struct Iter<'a, K, V, A = ()> {
+    walk: Walk<&'a AVLNode<K, V, A>, (&'a K, &'a V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V, A> Iter<'a, K, V, A> {
+    pub(crate) fn new(root: Option<&'a AVLNode<K, V, A>>, len: usize) -> Self {
+        Iter {
+            walk: Walk::new(root),
+            remaining: len,
+        }
+    }
+}
+
+/// Iterator over the entries of an `AvlMap` that can change the values.
+pub struct IterMut<'a, K, V, A = ()> {
+    walk: Walk<&'a mut AVLNode<K, V, A>, (&'a K, &'a mut V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V, A> IterMut<'a, K, V, A> {
+    pub(crate) fn new(root: Option<&'a mut AVLNode<K, V, A>>, len: usize) -> Self {
+        IterMut {
+            walk: Walk::new(root),
+            remaining: len,
+        }
+    }
+}
+
+/// Owning iterator over the entries of an `AvlMap`, in key order.
+pub struct IntoIter<K, V, A = ()> {
+    walk: Walk<AVLNode<K, V, A>, (K, V)>,
+    remaining: usize,
+}
+
+impl<K, V, A> IntoIter<K, V, A> {
+    pub(crate) fn new(root: Option<AVLNode<K, V, A>>, len: usize) -> Self {
+        IntoIter {
+            walk: Walk::new(root),
+            remaining: len,
+        }
+    }
+}
+
+macro_rules! walk_iterator {
+    ($name:ident<$($lt:lifetime,)? $($param:ident),*>, $item:ty, $split:expr) => {
+        impl<$($lt,)? $($param),*> Iterator for $name<$($lt,)? $($param),*> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let entry = self.walk.next($split)?;
+                self.remaining -= 1;
+                Some(entry)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.remaining, Some(self.remaining))
+            }
+        }
+
+        impl<$($lt,)? $($param),*> DoubleEndedIterator for $name<$($lt,)? $($param),*> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                let entry = self.walk.next_back($split)?;
+                self.remaining -= 1;
+                Some(entry)
+            }
+        }
+
+        impl<$($lt,)? $($param),*> ExactSizeIterator for $name<$($lt,)? $($param),*> {}
+
+        impl<$($lt,)? $($param),*> FusedIterator for $name<$($lt,)? $($param),*> {}
+    };
+}
+
+pub(crate) use walk_iterator;
+
+walk_iterator!(Iter<'a, K, V, A>, (&'a K, &'a V), split_ref);
+walk_iterator!(IterMut<'a, K, V, A>, (&'a K, &'a mut V), split_mut);
+walk_iterator!(IntoIter<K, V, A>, (K, V), split_owned);
+
+/// Borrowing iterator over the entries of an `AvlMap` whose keys fall in a
+/// range, in key order.
+pub struct Range<'a, K, V, A = ()> {
+    walk: Walk<&'a AVLNode<K, V, A>, (&'a K, &'a V)>,
+}
+
+impl<'a, K, V, A> Range<'a, K, V, A> {
+    pub(crate) fn new<Q, R>(root: Option<&'a AVLNode<K, V, A>>, range: &R) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range {
+            walk: Walk::range(root, split_ref, range),
+        }
+    }
+}
+
+impl<'a, K, V, A> Iterator for Range<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.next(split_ref)
+    }
+}
+
+impl<K, V, A> DoubleEndedIterator for Range<'_, K, V, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.walk.next_back(split_ref)
+    }
+}
+
+impl<K, V, A> FusedIterator for Range<'_, K, V, A> {}
+
+/// Iterator over the keys of an `AvlMap`, in order.
+pub struct Keys<'a, K, V, A = ()> {
+    pub(crate) inner: Iter<'a, K, V, A>,
+}
+
+/// Iterator over the values of an `AvlMap`, in key order.
+pub struct Values<'a, K, V, A = ()> {
+    pub(crate) inner: Iter<'a, K, V, A>,
+}
+
+/// Iterator over the values of an `AvlMap` that can change them.
+pub struct ValuesMut<'a, K, V, A = ()> {
+    pub(crate) inner: IterMut<'a, K, V, A>,
+}
+
+macro_rules! projected_iterator {
+    ($name:ident<$lt:lifetime, $($param:ident),*>, $item:ty, $project:expr) => {
+        impl<$lt, $($param),*> Iterator for $name<$lt, $($param),*> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.inner.next().map($project)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.inner.size_hint()
+            }
+        }
+
+        impl<$lt, $($param),*> DoubleEndedIterator for $name<$lt, $($param),*> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.inner.next_back().map($project)
+            }
+        }
+
+        impl<$lt, $($param),*> ExactSizeIterator for $name<$lt, $($param),*> {}
+
+        impl<$lt, $($param),*> FusedIterator for $name<$lt, $($param),*> {}
+    };
+}
+
+pub(crate) use projected_iterator;
+
+projected_iterator!(Keys<'a, K, V, A>, &'a K, |(key, _)| key);
+projected_iterator!(Values<'a, K, V, A>, &'a V, |(_, value)| value);
+projected_iterator!(ValuesMut<'a, K, V, A>, &'a mut V, |(_, value)| value);
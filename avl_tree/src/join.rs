@@ -0,0 +1,177 @@
+// Splitting and joining whole trees. Everything here is built on join, which
+// glues two trees either side of a pivot by walking down the taller one's
+// spine until the heights are close enough to hang the shorter tree there,
+// then rebalancing on the way back up. That costs O(difference in heights),
+// so split, which does one join per level, stays O(log n).
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use crate::augment::Augment;
+use crate::node::{find_max_and_delete, get_height, make_node, rebalance, AVLTree};
+use crate::observer::TreeObserver;
+
+// Every label in left must be less than label, and every label in right must
+// be greater.
+pub(crate) fn join<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    left: AVLTree<K, V, A>,
+    label: K,
+    value: V,
+    right: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let left_height = get_height(&left);
+    let right_height = get_height(&right);
+    if left_height > right_height + 1 {
+        join_right(left, label, value, right, observer)
+    } else if right_height > left_height + 1 {
+        join_left(left, label, value, right, observer)
+    } else {
+        make_node(left, label, value, right)
+    }
+}
+
+// left is the taller tree, so we go down its right spine.
+fn join_right<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    left: AVLTree<K, V, A>,
+    label: K,
+    value: V,
+    right: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let mut node = left.unwrap();
+    let spine = node.right.take();
+    node.right = if get_height(&spine) <= get_height(&right) + 1 {
+        make_node(spine, label, value, right)
+    } else {
+        join_right(spine, label, value, right, observer)
+    };
+    let mut tree = Some(node);
+    rebalance(&mut tree, observer);
+    tree
+}
+
+// right is the taller tree, so we go down its left spine.
+fn join_left<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    left: AVLTree<K, V, A>,
+    label: K,
+    value: V,
+    right: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let mut node = right.unwrap();
+    let spine = node.left.take();
+    node.left = if get_height(&spine) <= get_height(&left) + 1 {
+        make_node(left, label, value, spine)
+    } else {
+        join_left(left, label, value, spine, observer)
+    };
+    let mut tree = Some(node);
+    rebalance(&mut tree, observer);
+    tree
+}
+
+// Like join, but without a pivot: we borrow the largest entry of left.
+pub(crate) fn join_without_pivot<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    left: AVLTree<K, V, A>,
+    right: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    if left.is_none() {
+        return right;
+    }
+    let mut left = left;
+    let (label, value) = find_max_and_delete(&mut left, observer);
+    join(left, label, value, right, observer)
+}
+
+// Cuts the tree into the labels below target, the entry at target if there is
+// one, and the labels above target.
+#[allow(clippy::type_complexity)]
+pub(crate) fn split<K, V, A: Augment<K, V>, Q, O: TreeObserver<K>>(
+    tree: AVLTree<K, V, A>,
+    target: &Q,
+    observer: &mut O,
+) -> (AVLTree<K, V, A>, Option<(K, V)>, AVLTree<K, V, A>)
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    let node = match tree {
+        Some(node) => *node,
+        None => return (None, None, None),
+    };
+    match target.cmp(node.label.borrow()) {
+        Ordering::Less => {
+            let (below, found, above) = split(node.left, target, observer);
+            (
+                below,
+                found,
+                join(above, node.label, node.value, node.right, observer),
+            )
+        }
+        Ordering::Equal => (node.left, Some((node.label, node.value)), node.right),
+        Ordering::Greater => {
+            let (below, found, above) = split(node.right, target, observer);
+            (
+                join(node.left, node.label, node.value, below, observer),
+                found,
+                above,
+            )
+        }
+    }
+}
+
+// Everything in either tree. When both have a label, the entry from second
+// wins.
+pub(crate) fn union<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    first: AVLTree<K, V, A>,
+    second: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let node = match (first.is_some(), second) {
+        (_, None) => return first,
+        (false, second) => return second,
+        (true, Some(node)) => *node,
+    };
+    let (below, _, above) = split(first, &node.label, observer);
+    let left = union(below, node.left, observer);
+    let right = union(above, node.right, observer);
+    join(left, node.label, node.value, right, observer)
+}
+
+// Entries of first whose labels are also in second.
+pub(crate) fn intersection<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    first: AVLTree<K, V, A>,
+    second: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let node = match (first.is_some(), second) {
+        (true, Some(node)) => *node,
+        _ => return None,
+    };
+    let (below, found, above) = split(first, &node.label, observer);
+    let left = intersection(below, node.left, observer);
+    let right = intersection(above, node.right, observer);
+    match found {
+        Some((label, value)) => join(left, label, value, right, observer),
+        None => join_without_pivot(left, right, observer),
+    }
+}
+
+// Entries of first whose labels are not in second.
+pub(crate) fn difference<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>>(
+    first: AVLTree<K, V, A>,
+    second: AVLTree<K, V, A>,
+    observer: &mut O,
+) -> AVLTree<K, V, A> {
+    let node = match (first.is_some(), second) {
+        (false, _) => return None,
+        (true, None) => return first,
+        (true, Some(node)) => *node,
+    };
+    let (below, _, above) = split(first, &node.label, observer);
+    let left = difference(below, node.left, observer);
+    let right = difference(above, node.right, observer);
+    join_without_pivot(left, right, observer)
+}
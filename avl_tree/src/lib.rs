@@ -0,0 +1,29 @@
+//! A balanced binary search tree. `AvlMap` and `AvlSet` are the public faces;
+//! the node-level code they share lives in `node`. With the `serde` feature
+//! on, both serialize as a sequence in key order.
+
+pub mod arena;
+mod augment;
+mod check;
+pub mod interval;
+mod iter;
+mod join;
+pub mod map;
+mod multiset;
+mod node;
+mod observer;
+pub mod persistent;
+mod render;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod set;
+
+pub use arena::ArenaMap;
+pub use augment::Augment;
+pub use check::Violation;
+pub use interval::IntervalTree;
+pub use map::AvlMap;
+pub use multiset::AvlMultiset;
+pub use observer::{Rotation, TreeEvent, TreeObserver};
+pub use persistent::PersistentMap;
+pub use set::AvlSet;
@@ -0,0 +1,49 @@
+use std::fmt::Debug;
+
+use avl_tree::{AvlMap, Rotation, TreeObserver};
+
+// Says out loud everything the tree does to stay balanced.
+#[derive(Debug, Default)]
+struct Narrator;
+
+impl<K: Debug> TreeObserver<K> for Narrator {
+    fn rotated(&mut self, rotation: Rotation, pivot: &K) {
+        println!("I am rotating {0:?} from {1:?}", rotation, pivot);
+    }
+
+    fn rebalancing(&mut self, label: &K, balance_factor: i64) {
+        println!(
+            "{0:?} has a balance factor of {1}, so it needs rebalancing",
+            label, balance_factor
+        );
+    }
+
+    fn height_fixed(&mut self, label: &K, old: i64, new: i64) {
+        println!("The height of {0:?} went from {1} to {2}", label, old, new);
+    }
+}
+
+fn main() {
+    let mut int_tree: AvlMap<i64, (), (), Narrator> = AvlMap::with_observer(Narrator);
+    for n in 1..100 {
+        println!("Now inserting {0}", n);
+        int_tree.insert(n, ());
+        int_tree.validate();
+    }
+    println!("The tree is now of height {0:?},", int_tree.height());
+    int_tree.pretty_print();
+    println!(
+        "Does the tree contain 50? {0:?}",
+        int_tree.contains_key(&50)
+    );
+    println!(
+        "Does the tree contain 500? {0:?}",
+        int_tree.contains_key(&500)
+    );
+    for n in 1..100 {
+        println!("Now deleting {0}", n);
+        int_tree.remove(&n);
+        int_tree.validate();
+    }
+    println!("Can I Debug the tree? {0:?}", int_tree);
+}
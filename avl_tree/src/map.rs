@@ -0,0 +1,740 @@
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::ops::RangeBounds;
+
+use crate::augment::{self, Augment};
+use crate::check::{self, Violation};
+use crate::join;
+use crate::node::{self, AVLTree};
+use crate::observer::TreeObserver;
+use crate::render;
+
+pub use crate::iter::{IntoIter, Iter, IterMut, Keys, Range, Values, ValuesMut};
+
+/// An ordered map backed by an AVL tree. `A` is the summary kept for every
+/// subtree (see `Augment`) and `O` hears about every rotation (see
+/// `TreeObserver`); both default to `()`, which does nothing.
+#[derive(Debug)]
+pub struct AvlMap<K, V, A = (), O = ()> {
+    root: AVLTree<K, V, A>,
+    observer: O,
+}
+
+// Like HashMap::new, new is only for the default parameters, so that plain
+// maps need no type annotations; everything else starts from default() or
+// with_observer().
+impl<K, V> AvlMap<K, V> {
+    pub fn new() -> Self {
+        AvlMap {
+            root: None,
+            observer: (),
+        }
+    }
+}
+
+// The mutable accessors are only for unaugmented maps, because handing out
+// &mut V would let callers change values behind the summaries' backs.
+// Augmented maps use update.
+impl<K, V, O> AvlMap<K, V, (), O> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let len = self.len();
+        IterMut::new(self.root.as_deref_mut(), len)
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+}
+
+impl<K: Ord, V, O> AvlMap<K, V, (), O> {
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::get_mut(&mut self.root, key).map(|found| &mut found.value)
+    }
+}
+
+impl<K, V, A, O> AvlMap<K, V, A, O> {
+    /// An empty map that reports to `observer`.
+    pub fn with_observer(observer: O) -> Self {
+        AvlMap {
+            root: None,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub(crate) fn root(&self) -> &AVLTree<K, V, A> {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        node::get_size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    pub fn height(&self) -> i64 {
+        node::get_height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V, A> {
+        Iter::new(self.root.as_deref(), self.len())
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, A> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, A> {
+        Values { inner: self.iter() }
+    }
+
+    /// Iterates over the entries whose keys fall in `range`, in key order. A
+    /// range whose start is past its end is simply empty.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, A>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range::new(self.root.as_deref(), &range)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        node::find_min(&self.root).map(|found| (&found.label, &found.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        node::find_max(&self.root).map(|found| (&found.label, &found.value))
+    }
+
+    /// The entry with the greatest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::floor(&self.root, key).map(|found| (&found.label, &found.value))
+    }
+
+    /// The entry with the smallest key greater than or equal to `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::ceiling(&self.root, key).map(|found| (&found.label, &found.value))
+    }
+
+    /// The number of keys strictly less than `key`, whether or not `key` is
+    /// itself in the map.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::rank(&self.root, key)
+    }
+
+    /// The entry with the `index`-th smallest key, counting from zero.
+    pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+        node::select(&self.root, index).map(|found| (&found.label, &found.value))
+    }
+}
+
+impl<K: Debug, V, A, O> AvlMap<K, V, A, O> {
+    /// The tree in Graphviz's dot language, with each node's height and
+    /// balance factor under its key.
+    pub fn to_dot(&self) -> String {
+        render::to_dot(&self.root)
+    }
+
+    /// The tree drawn on its side, root at the left and bigger keys higher
+    /// up, with each node's height and balance factor.
+    pub fn pretty(&self) -> String {
+        render::pretty(&self.root)
+    }
+
+    /// Prints `pretty` to stdout.
+    pub fn pretty_print(&self) {
+        print!("{}", self.pretty());
+    }
+}
+
+impl<K, V, A: Augment<K, V>, O> AvlMap<K, V, A, O> {
+    /// Makes sure the keys are in order, every node is in balance, and every
+    /// height, size and summary is up to date. Returns the first problem it
+    /// finds.
+    pub fn check(&self) -> Result<(), Violation>
+    where
+        K: Ord,
+        A: PartialEq,
+    {
+        check::check(&self.root)
+    }
+
+    /// Panics with whatever `check` finds wrong.
+    pub fn validate(&self)
+    where
+        K: Ord,
+        A: PartialEq,
+    {
+        if let Err(violation) = self.check() {
+            panic!("{}", violation);
+        }
+    }
+
+    /// Every entry in the map folded together, in key order.
+    pub fn summary(&self) -> A {
+        node::get_summary(&self.root)
+    }
+
+    /// The entries whose keys fall in `range` folded together, in key order.
+    /// O(log n) however wide the range is.
+    pub fn aggregate<Q, R>(&self, range: R) -> A
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        augment::aggregate(&self.root, &range)
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>> AvlMap<K, V, A, O> {
+    /// Inserts a key and value, returning the value previously stored under
+    /// an equal key. The existing key is kept in that case.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        node::insert(&mut self.root, key, value, &mut self.observer)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::delete(&mut self.root, key, &mut self.observer)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        Some(node::find_min_and_delete(
+            &mut self.root,
+            &mut self.observer,
+        ))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        Some(node::find_max_and_delete(
+            &mut self.root,
+            &mut self.observer,
+        ))
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::get(&self.root, key).map(|found| &found.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::get(&self.root, key).map(|found| (&found.label, &found.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        node::get(&self.root, key).is_some()
+    }
+
+    /// Runs `f` on the value stored under `key` and brings the summaries back
+    /// up to date. Returns false, without calling `f`, if `key` is missing.
+    pub fn update<Q, F>(&mut self, key: &Q, f: F) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        F: FnOnce(&mut V),
+    {
+        node::update(&mut self.root, key, f)
+    }
+
+    /// Builds a map from `left`, the pivot entry and `right` in O(log n).
+    /// Panics unless every key in `left` is less than `key` and every key in
+    /// `right` is greater. The result keeps `left`'s observer.
+    pub fn join(left: Self, key: K, value: V, right: Self) -> Self {
+        if let Some((left_last, _)) = left.last() {
+            assert!(*left_last < key, "join: left keys must be below the pivot");
+        }
+        if let Some((right_first, _)) = right.first() {
+            assert!(
+                *right_first > key,
+                "join: right keys must be above the pivot"
+            );
+        }
+        let mut observer = left.observer;
+        AvlMap {
+            root: join::join(left.root, key, value, right.root, &mut observer),
+            observer,
+        }
+    }
+
+    /// Splits the map in two at `key`: afterwards `self` holds the keys less
+    /// than `key` and the returned map holds the rest. O(log n). The returned
+    /// map gets a fresh observer.
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        O: Default,
+    {
+        let observer = &mut self.observer;
+        let (below, found, above) = join::split(self.root.take(), key, observer);
+        self.root = below;
+        let root = match found {
+            Some((label, value)) => join::join(None, label, value, above, observer),
+            None => above,
+        };
+        AvlMap {
+            root,
+            observer: O::default(),
+        }
+    }
+
+    /// Moves every entry of `other` into `self`, leaving `other` empty. When a
+    /// key is in both, the value from `other` wins. If the two key ranges do
+    /// not overlap this is a single O(log n) join.
+    pub fn append(&mut self, other: &mut Self) {
+        let first = self.root.take();
+        let second = other.root.take();
+        let self_below_other = match (node::find_max(&first), node::find_min(&second)) {
+            (Some(max), Some(min)) => max.label < min.label,
+            _ => true,
+        };
+        let other_below_self = match (node::find_max(&second), node::find_min(&first)) {
+            (Some(max), Some(min)) => max.label < min.label,
+            _ => true,
+        };
+        let observer = &mut self.observer;
+        self.root = if self_below_other {
+            join::join_without_pivot(first, second, observer)
+        } else if other_below_self {
+            join::join_without_pivot(second, first, observer)
+        } else {
+            join::union(first, second, observer)
+        };
+    }
+
+    /// Everything in either map; values from `other` win on shared keys.
+    pub fn union(self, other: Self) -> Self {
+        let mut observer = self.observer;
+        AvlMap {
+            root: join::union(self.root, other.root, &mut observer),
+            observer,
+        }
+    }
+
+    /// The entries of `self` whose keys are also in `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        let mut observer = self.observer;
+        AvlMap {
+            root: join::intersection(self.root, other.root, &mut observer),
+            observer,
+        }
+    }
+
+    /// The entries of `self` whose keys are not in `other`.
+    pub fn difference(self, other: Self) -> Self {
+        let mut observer = self.observer;
+        AvlMap {
+            root: join::difference(self.root, other.root, &mut observer),
+            observer,
+        }
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, O: Default> AvlMap<K, V, A, O> {
+    /// Builds a perfectly balanced map in O(n) from entries that are already
+    /// in strictly increasing key order. Panics if they are not.
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let entries: Vec<(K, V)> = iter.into_iter().collect();
+        assert!(
+            is_strictly_sorted(&entries),
+            "from_sorted_iter: keys must be strictly increasing"
+        );
+        AvlMap {
+            root: node::build_balanced(entries.len(), &mut entries.into_iter()),
+            observer: O::default(),
+        }
+    }
+}
+
+fn is_strictly_sorted<K: Ord, V>(entries: &[(K, V)]) -> bool {
+    entries.windows(2).all(|pair| pair[0].0 < pair[1].0)
+}
+
+impl<K, V, A, O> FromIterator<(K, V)> for AvlMap<K, V, A, O>
+where
+    K: Ord,
+    A: Augment<K, V>,
+    O: TreeObserver<K> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = AvlMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>, O: TreeObserver<K>> Extend<(K, V)> for AvlMap<K, V, A, O> {
+    // If the new entries are sorted and all come after what we already have,
+    // we build them into a tree of their own and join it on in one go.
+    // Anything else goes in one insert at a time.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let entries: Vec<(K, V)> = iter.into_iter().collect();
+        let goes_after = match (self.last(), entries.first()) {
+            (Some((last, _)), Some((first, _))) => last < first,
+            _ => true,
+        };
+        if goes_after && is_strictly_sorted(&entries) {
+            let tail = node::build_balanced(entries.len(), &mut entries.into_iter());
+            self.root = join::join_without_pivot(self.root.take(), tail, &mut self.observer);
+        } else {
+            for (key, value) in entries {
+                self.insert(key, value);
+            }
+        }
+    }
+}
+
+impl<K, V, A, O: Default> Default for AvlMap<K, V, A, O> {
+    fn default() -> Self {
+        AvlMap::with_observer(O::default())
+    }
+}
+
+impl<K, V, A, O> IntoIterator for AvlMap<K, V, A, O> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    fn into_iter(self) -> IntoIter<K, V, A> {
+        let len = self.len();
+        IntoIter::new(self.root.map(|root| *root), len)
+    }
+}
+
+impl<'a, K, V, A, O> IntoIterator for &'a AvlMap<K, V, A, O> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, A>;
+
+    fn into_iter(self) -> Iter<'a, K, V, A> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, O> IntoIterator for &'a mut AvlMap<K, V, (), O> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Bound::{Excluded, Included};
+
+    #[test]
+    fn test_insert_returns_previous_value() {
+        let mut map: AvlMap<&str, i64> = AvlMap::new();
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 3), Some(1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+        map.validate();
+    }
+
+    #[test]
+    fn test_remove_missing_key() {
+        let mut map: AvlMap<i64, i64> = AvlMap::new();
+        assert_eq!(map.remove(&1), None);
+        map.insert(1, 10);
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.remove(&1), Some(10));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map: AvlMap<String, Vec<i64>> = AvlMap::new();
+        map.insert("x".to_string(), vec![]);
+        map.get_mut("x").unwrap().push(5);
+        assert_eq!(map.get("x"), Some(&vec![5]));
+        assert_eq!(map.get_mut("y"), None);
+    }
+
+    #[test]
+    fn test_insert_and_remove_stay_balanced() {
+        let mut map: AvlMap<i64, i64> = AvlMap::new();
+        for n in 0..200 {
+            map.insert((n * 37) % 200, n);
+            map.validate();
+        }
+        assert_eq!(map.len(), 200);
+        for n in 0..200 {
+            assert_eq!(map.remove(&((n * 37) % 200)), Some(n));
+            map.validate();
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.height(), 0);
+    }
+
+    #[test]
+    fn test_iterators_in_both_directions() {
+        let mut map: AvlMap<String, usize> = AvlMap::new();
+        for word in ["pear", "apple", "fig", "kiwi", "banana", "cherry"] {
+            map.insert(word.to_string(), word.len());
+        }
+        let keys: Vec<&str> = map.keys().map(|key| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["apple", "banana", "cherry", "fig", "kiwi", "pear"]
+        );
+        let backwards: Vec<&str> = map.keys().rev().map(|key| key.as_str()).collect();
+        assert_eq!(
+            backwards,
+            vec!["pear", "kiwi", "fig", "cherry", "banana", "apple"]
+        );
+
+        for (_, value) in map.iter_mut() {
+            *value *= 10;
+        }
+        assert_eq!(map.values().sum::<usize>(), 280);
+
+        let mut owned = map.into_iter();
+        assert_eq!(owned.len(), 6);
+        assert_eq!(owned.next(), Some(("apple".to_string(), 50)));
+        assert_eq!(owned.next_back(), Some(("pear".to_string(), 40)));
+        let middle: Vec<String> = owned.map(|(key, _)| key).collect();
+        assert_eq!(middle, vec!["banana", "cherry", "fig", "kiwi"]);
+    }
+
+    #[test]
+    fn test_iter_ends_meet_in_the_middle() {
+        let mut map: AvlMap<i64, ()> = AvlMap::new();
+        for n in 0..100 {
+            map.insert(n, ());
+        }
+        let mut iter = map.keys();
+        let mut seen = vec![];
+        while let Some(front) = iter.next() {
+            seen.push(*front);
+            if let Some(back) = iter.next_back() {
+                seen.push(*back);
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, (0..100).collect::<Vec<i64>>());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut map: AvlMap<i64, i64> = AvlMap::new();
+        for n in 0..50 {
+            map.insert(n * 2, n);
+        }
+        let keys = |range: Range<i64, i64>| range.map(|(key, _)| *key).collect::<Vec<i64>>();
+        assert_eq!(keys(map.range(10..16)), vec![10, 12, 14]);
+        assert_eq!(keys(map.range(9..=16)), vec![10, 12, 14, 16]);
+        assert_eq!(keys(map.range(95..)), vec![96, 98]);
+        assert_eq!(keys(map.range(..3)), vec![0, 2]);
+        assert_eq!(keys(map.range(11..11)), vec![]);
+        assert_eq!(keys(map.range((Included(20), Excluded(10)))), vec![]);
+        assert_eq!(map.range(..).count(), 50);
+        let backwards: Vec<i64> = map.range(40..=50).rev().map(|(key, _)| *key).collect();
+        assert_eq!(backwards, vec![50, 48, 46, 44, 42, 40]);
+    }
+
+    #[test]
+    fn test_floor_ceiling_first_last() {
+        let mut map: AvlMap<i64, char> = AvlMap::new();
+        assert_eq!(map.first(), None);
+        assert_eq!(map.pop_last(), None);
+        for (key, value) in [(10, 'a'), (20, 'b'), (30, 'c')] {
+            map.insert(key, value);
+        }
+        assert_eq!(map.floor(&25), Some((&20, &'b')));
+        assert_eq!(map.floor(&20), Some((&20, &'b')));
+        assert_eq!(map.floor(&5), None);
+        assert_eq!(map.ceiling(&25), Some((&30, &'c')));
+        assert_eq!(map.ceiling(&35), None);
+        assert_eq!(map.first(), Some((&10, &'a')));
+        assert_eq!(map.last(), Some((&30, &'c')));
+        assert_eq!(map.pop_first(), Some((10, 'a')));
+        assert_eq!(map.pop_last(), Some((30, 'c')));
+        assert_eq!(map.len(), 1);
+        map.validate();
+    }
+
+    #[test]
+    fn test_rank_and_select() {
+        let mut map: AvlMap<i64, i64> = AvlMap::new();
+        for n in (0..100).rev() {
+            map.insert(n * 10, n);
+        }
+        for n in 0..100 {
+            assert_eq!(map.select(n as usize), Some((&(n * 10), &n)));
+            assert_eq!(map.rank(&(n * 10)), n as usize);
+            assert_eq!(map.rank(&(n * 10 + 5)), n as usize + 1);
+        }
+        assert_eq!(map.select(100), None);
+        for n in 0..50 {
+            map.remove(&(n * 20));
+        }
+        map.validate();
+        assert_eq!(map.len(), 50);
+        assert_eq!(map.select(0), Some((&10, &1)));
+        assert_eq!(map.rank(&500), 25);
+    }
+
+    fn map_of(keys: impl Iterator<Item = i64>) -> AvlMap<i64, i64> {
+        let mut map = AvlMap::new();
+        for key in keys {
+            map.insert(key, key * 100);
+        }
+        map
+    }
+
+    #[test]
+    fn test_split_off_and_append() {
+        let mut map = map_of(0..300);
+        let mut upper = map.split_off(&120);
+        map.validate();
+        upper.validate();
+        assert_eq!(map.len(), 120);
+        assert_eq!(upper.len(), 180);
+        assert_eq!(map.last(), Some((&119, &11900)));
+        assert_eq!(upper.first(), Some((&120, &12000)));
+
+        let mut tiny = upper.split_off(&1000);
+        assert!(tiny.is_empty());
+        map.append(&mut upper);
+        map.append(&mut tiny);
+        map.validate();
+        assert!(upper.is_empty());
+        assert_eq!(
+            map.keys().copied().collect::<Vec<i64>>(),
+            (0..300).collect::<Vec<i64>>()
+        );
+    }
+
+    #[test]
+    fn test_append_overlapping() {
+        let mut evens = map_of((0..100).map(|n| n * 2));
+        let mut others = map_of(50..150);
+        others.insert(60, -1);
+        evens.append(&mut others);
+        evens.validate();
+        assert_eq!(evens.len(), 50 + 100);
+        assert_eq!(evens.get(&60), Some(&-1));
+    }
+
+    #[test]
+    fn test_join_uneven_heights() {
+        let joined = AvlMap::join(map_of(0..3), 3, 300, map_of(4..1000));
+        joined.validate();
+        assert_eq!(joined.len(), 1000);
+        assert_eq!(joined.select(3), Some((&3, &300)));
+        let joined = AvlMap::join(map_of(0..1000), 1000, 0, AvlMap::new());
+        joined.validate();
+        assert_eq!(joined.len(), 1001);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let threes = || map_of((0..100).map(|n| n * 3));
+        let fives = || map_of((0..100).map(|n| n * 5));
+        let union = threes().union(fives());
+        union.validate();
+        assert_eq!(union.len(), 100 + 100 - 20);
+        let intersection = threes().intersection(fives());
+        intersection.validate();
+        let expected: Vec<i64> = (0..20).map(|n| n * 15).collect();
+        assert_eq!(intersection.keys().copied().collect::<Vec<i64>>(), expected);
+        let difference = threes().difference(fives());
+        difference.validate();
+        assert_eq!(difference.len(), 80);
+        assert!(!difference.contains_key(&15));
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        for len in 0..70 {
+            let map: AvlMap<i64, i64> = AvlMap::from_sorted_iter((0..len).map(|n| (n, n * n)));
+            map.validate();
+            assert_eq!(map.len(), len as usize);
+            assert_eq!(
+                map.values().copied().collect::<Vec<i64>>(),
+                (0..len).map(|n| n * n).collect::<Vec<i64>>()
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn test_from_sorted_iter_rejects_duplicates() {
+        let _: AvlMap<i64, char> = AvlMap::from_sorted_iter(vec![(1, 'a'), (1, 'b')]);
+    }
+
+    #[test]
+    fn test_collect_and_extend() {
+        let mut map: AvlMap<i64, char> = vec![(3, 'c'), (1, 'a'), (2, 'b'), (1, 'z')]
+            .into_iter()
+            .collect();
+        map.validate();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(&'z'));
+        map.extend((10..20).map(|n| (n, 'x')));
+        map.extend(vec![(5, 'e'), (4, 'd')]);
+        map.validate();
+        assert_eq!(map.len(), 15);
+        assert_eq!(map.rank(&10), 5);
+    }
+}
@@ -0,0 +1,246 @@
+use std::borrow::Borrow;
+use std::ops::Bound;
+
+use crate::augment::Augment;
+use crate::map::AvlMap;
+use crate::node::{get_summary, AVLTree};
+
+// How many copies there are in a subtree, counting duplicates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Total(usize);
+
+impl<K> Augment<K, usize> for Total {
+    fn identity() -> Self {
+        Total(0)
+    }
+
+    fn single(_key: &K, count: &usize) -> Self {
+        Total(*count)
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Total(self.0 + other.0)
+    }
+}
+
+/// An ordered collection that can hold the same key more than once. Each
+/// distinct key gets one node with a count, so equal keys are interchangeable
+/// and only the first one inserted is kept.
+#[derive(Debug)]
+pub struct AvlMultiset<K> {
+    counts: AvlMap<K, usize, Total>,
+}
+
+impl<K> AvlMultiset<K> {
+    pub fn new() -> Self {
+        AvlMultiset {
+            counts: AvlMap::default(),
+        }
+    }
+
+    /// The number of copies, counting duplicates.
+    pub fn len(&self) -> usize {
+        get_summary(self.counts.root()).0
+    }
+
+    /// The number of different keys.
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Every copy, in order, with duplicates next to each other.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &K> + '_ {
+        self.counts
+            .iter()
+            .flat_map(|(key, count)| std::iter::repeat_n(key, *count))
+    }
+
+    /// Each different key once, with how many copies of it there are.
+    pub fn iter_counts(&self) -> impl DoubleEndedIterator<Item = (&K, usize)> + '_ {
+        self.counts.iter().map(|(key, count)| (key, *count))
+    }
+
+    /// The copy at position `index` if we listed every copy in order.
+    pub fn select(&self, index: usize) -> Option<&K> {
+        select(self.counts.root(), index)
+    }
+}
+
+impl<K: Ord> AvlMultiset<K> {
+    /// Panics if the tree is out of balance, a count is zero, or a subtree's
+    /// total is stale.
+    pub fn validate(&self) {
+        self.counts.validate();
+        check_totals(self.counts.root());
+    }
+
+    /// Adds one more copy of `key` and returns how many there are now.
+    pub fn insert(&mut self, key: K) -> usize {
+        let mut now = 1;
+        let found = self.counts.update(&key, |count| {
+            *count += 1;
+            now = *count;
+        });
+        if !found {
+            self.counts.insert(key, 1);
+        }
+        now
+    }
+
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.counts.contains_key(key)
+    }
+
+    /// Removes a single copy of `key`. Returns false if there was none.
+    pub fn remove_one<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.counts.get(key) {
+            None => false,
+            Some(1) => {
+                self.counts.remove(key);
+                true
+            }
+            Some(_) => self.counts.update(key, |count| *count -= 1),
+        }
+    }
+
+    /// Removes every copy of `key` and returns how many there were.
+    pub fn remove_all<Q>(&mut self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.counts.remove(key).unwrap_or(0)
+    }
+
+    /// The number of copies strictly less than `key`.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.counts
+            .aggregate::<Q, _>((Bound::Unbounded, Bound::Excluded(key)))
+            .0
+    }
+}
+
+impl<K> Default for AvlMultiset<K> {
+    fn default() -> Self {
+        AvlMultiset::new()
+    }
+}
+
+impl<K: Ord> FromIterator<K> for AvlMultiset<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut multiset = AvlMultiset::new();
+        for key in iter {
+            multiset.insert(key);
+        }
+        multiset
+    }
+}
+
+// Like node::select, but stepping over whole counts instead of single nodes.
+fn select<K>(tree: &AVLTree<K, usize, Total>, index: usize) -> Option<&K> {
+    let mut index = index;
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        let left_total = get_summary(&node.left).0;
+        if index < left_total {
+            current = node.left.as_deref();
+        } else if index < left_total + node.value {
+            return Some(&node.label);
+        } else {
+            index -= left_total + node.value;
+            current = node.right.as_deref();
+        }
+    }
+    None
+}
+
+fn check_totals<K>(tree: &AVLTree<K, usize, Total>) -> usize {
+    let node = match tree {
+        Some(node) => node,
+        None => return 0,
+    };
+    if node.value == 0 {
+        panic!("A key should never be left in the tree with a count of zero.");
+    }
+    let correct = check_totals(&node.left) + node.value + check_totals(&node.right);
+    if correct != node.summary.0 {
+        panic!(
+            "My total should say {} but instead it says {}",
+            correct, node.summary.0
+        );
+    }
+    correct
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates() {
+        let mut bag: AvlMultiset<char> = AvlMultiset::new();
+        assert_eq!(bag.insert('b'), 1);
+        assert_eq!(bag.insert('a'), 1);
+        assert_eq!(bag.insert('b'), 2);
+        assert_eq!(bag.insert('c'), 1);
+        assert_eq!(bag.insert('b'), 3);
+        bag.validate();
+        assert_eq!(bag.len(), 5);
+        assert_eq!(bag.distinct_len(), 3);
+        assert_eq!(bag.count(&'b'), 3);
+        assert_eq!(bag.count(&'z'), 0);
+        assert_eq!(bag.iter().collect::<String>(), "abbbc");
+        assert_eq!(bag.iter().rev().collect::<String>(), "cbbba");
+        assert_eq!(bag.rank(&'c'), 4);
+        assert_eq!(bag.select(3), Some(&'b'));
+        assert_eq!(bag.select(4), Some(&'c'));
+        assert_eq!(bag.select(5), None);
+
+        assert!(bag.remove_one(&'b'));
+        assert!(bag.remove_one(&'a'));
+        assert!(!bag.remove_one(&'a'));
+        bag.validate();
+        assert_eq!(bag.iter().collect::<String>(), "bbc");
+        assert_eq!(bag.remove_all(&'b'), 2);
+        assert_eq!(bag.len(), 1);
+        bag.validate();
+    }
+
+    #[test]
+    fn test_many_duplicates_stay_balanced() {
+        let mut bag: AvlMultiset<i64> = (0..1000).map(|n| n % 37).collect();
+        bag.validate();
+        assert_eq!(bag.len(), 1000);
+        assert_eq!(bag.distinct_len(), 37);
+        for n in 0..500 {
+            assert!(bag.remove_one(&(n % 37)));
+        }
+        bag.validate();
+        assert_eq!(bag.len(), 500);
+        assert_eq!(bag.rank(&1), bag.count(&0));
+    }
+}
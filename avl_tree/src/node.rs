@@ -0,0 +1,615 @@
+use std::borrow::Borrow;
+use std::cmp::{max, Ordering};
+use std::fmt::{self, Debug};
+use std::mem;
+use std::ops::Deref;
+
+use crate::augment::Augment;
+use crate::observer::{Rotation, TreeObserver};
+
+pub(crate) type AVLTree<K, V, A = ()> = Option<Box<AVLNode<K, V, A>>>;
+
+/// Which kind of pointer a node keeps its children behind. `Owned` is a Box;
+/// the persistent map has its own kind that shares children with Arcs.
+pub(crate) trait LinkKind<K, V, A>: Sized {
+    type Link: Deref<Target = AVLNode<K, V, A, Self>>;
+}
+
+/// A pointer to a child that the balancing code below can change in place.
+/// Box just hands out its node. Arc::make_mut copies the node first if some
+/// other version can still see it.
+pub(crate) trait Link<K, V, A>:
+    Deref<Target = AVLNode<K, V, A, Self::Kind>> + Sized
+{
+    type Kind: LinkKind<K, V, A, Link = Self>;
+
+    fn new(node: AVLNode<K, V, A, Self::Kind>) -> Self;
+
+    fn make_mut(&mut self) -> &mut AVLNode<K, V, A, Self::Kind>;
+
+    fn into_inner(self) -> AVLNode<K, V, A, Self::Kind>;
+}
+
+pub(crate) struct Owned;
+
+impl<K, V, A> LinkKind<K, V, A> for Owned {
+    type Link = Box<AVLNode<K, V, A>>;
+}
+
+impl<K, V, A> Link<K, V, A> for Box<AVLNode<K, V, A>> {
+    type Kind = Owned;
+
+    fn new(node: AVLNode<K, V, A>) -> Self {
+        Box::new(node)
+    }
+
+    fn make_mut(&mut self) -> &mut AVLNode<K, V, A> {
+        self
+    }
+
+    fn into_inner(self) -> AVLNode<K, V, A> {
+        *self
+    }
+}
+
+pub(crate) struct AVLNode<K, V, A = (), L: LinkKind<K, V, A> = Owned> {
+    pub(crate) label: K,
+    pub(crate) value: V,
+    pub(crate) height: i64,
+    // How many nodes are in this subtree, counting this one.
+    pub(crate) size: usize,
+    // Everything in this subtree folded together with Augment::combine.
+    pub(crate) summary: A,
+    pub(crate) left: Option<L::Link>,
+    pub(crate) right: Option<L::Link>,
+}
+
+// Written out because derive would want the link itself to be Debug, and for
+// a Box of this same node that goes around in a circle.
+impl<K: Debug, V: Debug, A: Debug, L: LinkKind<K, V, A>> Debug for AVLNode<K, V, A, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AVLNode")
+            .field("label", &self.label)
+            .field("value", &self.value)
+            .field("height", &self.height)
+            .field("size", &self.size)
+            .field("summary", &self.summary)
+            .field("left", &self.left.as_deref())
+            .field("right", &self.right.as_deref())
+            .finish()
+    }
+}
+
+pub(crate) fn get_height<K, V, A, L, P>(tree: &Option<P>) -> i64
+where
+    L: LinkKind<K, V, A>,
+    P: Deref<Target = AVLNode<K, V, A, L>>,
+{
+    match tree {
+        Some(node) => node.height,
+        None => 0,
+    }
+}
+
+pub(crate) fn get_size<K, V, A, L, P>(tree: &Option<P>) -> usize
+where
+    L: LinkKind<K, V, A>,
+    P: Deref<Target = AVLNode<K, V, A, L>>,
+{
+    match tree {
+        Some(node) => node.size,
+        None => 0,
+    }
+}
+
+pub(crate) fn balance_factor<K, V, A, L, P>(tree: &Option<P>) -> i64
+where
+    L: LinkKind<K, V, A>,
+    P: Deref<Target = AVLNode<K, V, A, L>>,
+{
+    match tree {
+        Some(node) => get_height(&node.left) - get_height(&node.right),
+        None => 0,
+    }
+}
+
+pub(crate) fn rebalance<K: Ord, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) {
+    fix_height(tree, observer); // we could be balanced but still wrong
+    let bf = balance_factor(tree);
+    if bf.abs() == 2 {
+        observer.rebalancing(&tree.as_ref().unwrap().label, bf);
+    }
+    match bf {
+        -1..=1 => (),
+        -2 => rotate_left_maybe_double(tree, observer),
+        2 => rotate_right_maybe_double(tree, observer),
+        // Both children were balanced before this one insert or delete,
+        // which moves a height by at most one, so we can only be off by two.
+        x => unreachable!("rebalance: balance factor {} after one change", x),
+    }
+}
+
+// Returns the value that was already stored under new_label, if any. In that
+// case the old label stays in the tree and only the value is swapped.
+pub(crate) fn insert<K: Ord, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    tree: &mut Option<P>,
+    new_label: K,
+    new_value: V,
+    observer: &mut O,
+) -> Option<V> {
+    let option_amp_mut: Option<&mut P> = tree.as_mut();
+    if let Some(link) = option_amp_mut {
+        let mut_box = link.make_mut();
+        let this_label: &K = &mut_box.label;
+        let child: &mut Option<P> = if new_label < *this_label {
+            &mut mut_box.left
+        } else if new_label > *this_label {
+            &mut mut_box.right
+        } else {
+            // Nothing moves, so there is nothing to rebalance, but the new
+            // value can still change our summary.
+            let old_value = mem::replace(&mut mut_box.value, new_value);
+            refresh(tree);
+            return Some(old_value);
+        };
+        let old_value = insert(child, new_label, new_value, observer);
+        rebalance(tree, observer);
+        old_value
+    } else {
+        *tree = singleton(new_label, new_value);
+        None
+    }
+}
+
+pub(crate) fn singleton<K, V, A: Augment<K, V>, P: Link<K, V, A>>(
+    new_label: K,
+    new_value: V,
+) -> Option<P> {
+    let summary = A::single(&new_label, &new_value);
+    let new_node = AVLNode {
+        label: new_label,
+        value: new_value,
+        height: 1,
+        size: 1,
+        summary,
+        left: None,
+        right: None,
+    };
+    Some(P::new(new_node))
+}
+
+pub(crate) fn make_node<K, V, A: Augment<K, V>>(
+    left: AVLTree<K, V, A>,
+    label: K,
+    value: V,
+    right: AVLTree<K, V, A>,
+) -> AVLTree<K, V, A> {
+    let mut tree = Some(Box::new(AVLNode {
+        label,
+        value,
+        height: 0,
+        size: 0,
+        summary: A::identity(),
+        left,
+        right,
+    }));
+    refresh(&mut tree);
+    tree
+}
+
+// Builds a tree out of the next len entries, which must already be in order.
+// Each node gets half of what is left on either side, so the subtree sizes
+// differ by at most one and no rotations are ever needed.
+pub(crate) fn build_balanced<K, V, A: Augment<K, V>>(
+    len: usize,
+    entries: &mut impl Iterator<Item = (K, V)>,
+) -> AVLTree<K, V, A> {
+    if len == 0 {
+        return None;
+    }
+    let left_len = len / 2;
+    let left = build_balanced(left_len, entries);
+    let (label, value) = entries.next().unwrap();
+    let right = build_balanced(len - left_len - 1, entries);
+    make_node(left, label, value, right)
+}
+
+pub(crate) fn get<'a, K, V, A, Q>(
+    tree: &'a AVLTree<K, V, A>,
+    target: &Q,
+) -> Option<&'a AVLNode<K, V, A>>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let option_amp_box: Option<&Box<AVLNode<K, V, A>>> = tree.as_ref();
+    if let Some(amp_box) = option_amp_box {
+        let this_label: &Q = amp_box.label.borrow();
+        if *target == *this_label {
+            return Some(amp_box);
+        }
+        let child: &AVLTree<K, V, A> = if *target < *this_label {
+            &amp_box.left
+        } else {
+            &amp_box.right
+        };
+        get(child, target)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn get_mut<'a, K, V, A, Q>(
+    tree: &'a mut AVLTree<K, V, A>,
+    target: &Q,
+) -> Option<&'a mut AVLNode<K, V, A>>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let option_amp_mut: Option<&mut Box<AVLNode<K, V, A>>> = tree.as_mut();
+    if let Some(mut_box) = option_amp_mut {
+        let this_label: &Q = mut_box.label.borrow();
+        if *target == *this_label {
+            return Some(mut_box);
+        }
+        let child: &mut AVLTree<K, V, A> = if *target < *this_label {
+            &mut mut_box.left
+        } else {
+            &mut mut_box.right
+        };
+        get_mut(child, target)
+    } else {
+        None
+    }
+}
+
+fn rotate_left<K, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    old_root_x: &mut Option<P>,
+    observer: &mut O,
+) {
+    let mut old_root_link = match old_root_x.take() {
+        Some(link) => link,
+        None => return,
+    };
+    let old_root = old_root_link.make_mut();
+    // We only ever rotate toward the shorter side, so the right child we are
+    // bringing up is the taller one and can't be missing.
+    let Some(mut new_root_link) = old_root.right.take() else {
+        unreachable!("rotate_left: no right child to bring up");
+    };
+    let new_root = new_root_link.make_mut();
+    observer.rotated(Rotation::Left, &old_root.label);
+    old_root.right = new_root.left.take();
+    new_root.left = Some(old_root_link);
+    fix_height(&mut new_root.left, observer);
+    *old_root_x = Some(new_root_link);
+    fix_height(old_root_x, observer);
+}
+
+fn rotate_right<K, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    old_root_x: &mut Option<P>,
+    observer: &mut O,
+) {
+    let mut old_root_link = match old_root_x.take() {
+        Some(link) => link,
+        None => return,
+    };
+    let old_root = old_root_link.make_mut();
+    // We only ever rotate toward the shorter side, so the left child we are
+    // bringing up is the taller one and can't be missing.
+    let Some(mut new_root_link) = old_root.left.take() else {
+        unreachable!("rotate_right: no left child to bring up");
+    };
+    let new_root = new_root_link.make_mut();
+    observer.rotated(Rotation::Right, &old_root.label);
+    old_root.left = new_root.right.take();
+    new_root.right = Some(old_root_link);
+    fix_height(&mut new_root.right, observer);
+    *old_root_x = Some(new_root_link);
+    fix_height(old_root_x, observer);
+}
+
+fn rotate_left_maybe_double<K, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) {
+    if tree.as_ref().is_none() {
+        return;
+    }
+    let node = tree.as_mut().unwrap().make_mut();
+    if balance_factor(&node.right) > 0 {
+        // Our right child leans the other way, so it gets straightened out
+        // first.
+        rotate_right(&mut node.right, observer);
+        fix_height(tree, observer);
+    }
+    rotate_left(tree, observer);
+}
+
+fn rotate_right_maybe_double<K, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) {
+    if tree.as_ref().is_none() {
+        return;
+    }
+    let node = tree.as_mut().unwrap().make_mut();
+    if balance_factor(&node.left) < 0 {
+        rotate_left(&mut node.left, observer);
+        fix_height(tree, observer);
+    }
+    rotate_right(tree, observer);
+}
+
+// Like refresh, but tells the observer if the height turned out to be stale.
+pub(crate) fn fix_height<K, V, A: Augment<K, V>, P: Link<K, V, A>, O: TreeObserver<K>>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) {
+    let old_height = get_height(tree);
+    refresh(tree);
+    if let Some(node) = tree.as_ref() {
+        if node.height != old_height {
+            observer.height_fixed(&node.label, old_height, node.height);
+        }
+    }
+}
+
+pub(crate) fn refresh<K, V, A: Augment<K, V>, P: Link<K, V, A>>(tree: &mut Option<P>) {
+    // This only works if your two subtrees have accurate height. So fix them
+    // first.
+    let option_amp_mut: Option<&mut P> = tree.as_mut();
+    if let Some(link) = option_amp_mut {
+        let mut_box = link.make_mut();
+        let left_height = get_height(&mut_box.left);
+        let right_height = get_height(&mut_box.right);
+        let new_height = 1 + max(left_height, right_height);
+        mut_box.height = new_height;
+        // Size and summary have the same dependency on the subtrees, so they
+        // ride along.
+        mut_box.size = 1 + get_size(&mut_box.left) + get_size(&mut_box.right);
+        mut_box.summary = get_summary(&mut_box.left)
+            .combine(&A::single(&mut_box.label, &mut_box.value))
+            .combine(&get_summary(&mut_box.right));
+    }
+}
+
+pub(crate) fn get_summary<K, V, A, L, P>(tree: &Option<P>) -> A
+where
+    A: Augment<K, V>,
+    L: LinkKind<K, V, A>,
+    P: Deref<Target = AVLNode<K, V, A, L>>,
+{
+    match tree {
+        Some(node) => node.summary.clone(),
+        None => A::identity(),
+    }
+}
+
+// Changes the value stored under target in place, then fixes up the summaries
+// on the way back to the root. Returns false if target is not in the tree.
+pub(crate) fn update<K, V, A, Q, F>(tree: &mut AVLTree<K, V, A>, target: &Q, f: F) -> bool
+where
+    K: Borrow<Q>,
+    A: Augment<K, V>,
+    Q: Ord + ?Sized,
+    F: FnOnce(&mut V),
+{
+    let node = match tree.as_mut() {
+        Some(node) => node,
+        None => return false,
+    };
+    let found = match target.cmp(node.label.borrow()) {
+        Ordering::Less => update(&mut node.left, target, f),
+        Ordering::Greater => update(&mut node.right, target, f),
+        Ordering::Equal => {
+            f(&mut node.value);
+            true
+        }
+    };
+    if found {
+        refresh(tree);
+    }
+    found
+}
+
+pub(crate) fn find_min_and_delete<
+    K: Ord,
+    V,
+    A: Augment<K, V>,
+    P: Link<K, V, A>,
+    O: TreeObserver<K>,
+>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) -> (K, V) {
+    if tree.as_ref().is_none() {
+        panic!("Don't call find_min_and_delete on an empty tree.");
+    }
+    let node = tree.as_mut().unwrap().make_mut();
+    if node.left.as_ref().is_some() {
+        let to_return = find_min_and_delete(&mut node.left, observer);
+        rebalance(tree, observer);
+        return to_return;
+    }
+    // we are at the minimum node. we promote our right child (empty or
+    // not) and return the label.
+    let right_child = node.right.take();
+    let this_one = tree.take().unwrap().into_inner();
+    *tree = right_child;
+    // I think our height is correct here? We have the height of the right
+    // child. If it was right before it's right now. If it was balanced before
+    // it's balanced now.
+    (this_one.label, this_one.value)
+}
+
+pub(crate) fn find_max_and_delete<
+    K: Ord,
+    V,
+    A: Augment<K, V>,
+    P: Link<K, V, A>,
+    O: TreeObserver<K>,
+>(
+    tree: &mut Option<P>,
+    observer: &mut O,
+) -> (K, V) {
+    if tree.as_ref().is_none() {
+        panic!("Don't call find_max_and_delete on an empty tree.");
+    }
+    let node = tree.as_mut().unwrap().make_mut();
+    if node.right.as_ref().is_some() {
+        let to_return = find_max_and_delete(&mut node.right, observer);
+        rebalance(tree, observer);
+        return to_return;
+    }
+    // The mirror image of find_min_and_delete: we promote our left child.
+    let left_child = node.left.take();
+    let this_one = tree.take().unwrap().into_inner();
+    *tree = left_child;
+    (this_one.label, this_one.value)
+}
+
+pub(crate) fn find_min<K, V, A>(tree: &AVLTree<K, V, A>) -> Option<&AVLNode<K, V, A>> {
+    let mut node = tree.as_deref()?;
+    while let Some(left) = node.left.as_deref() {
+        node = left;
+    }
+    Some(node)
+}
+
+pub(crate) fn find_max<K, V, A>(tree: &AVLTree<K, V, A>) -> Option<&AVLNode<K, V, A>> {
+    let mut node = tree.as_deref()?;
+    while let Some(right) = node.right.as_deref() {
+        node = right;
+    }
+    Some(node)
+}
+
+// The node with the greatest label that is <= target.
+pub(crate) fn floor<'a, K, V, A, Q>(
+    tree: &'a AVLTree<K, V, A>,
+    target: &Q,
+) -> Option<&'a AVLNode<K, V, A>>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let mut best = None;
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        if *node.label.borrow() <= *target {
+            best = Some(node);
+            current = node.right.as_deref();
+        } else {
+            current = node.left.as_deref();
+        }
+    }
+    best
+}
+
+// The node with the smallest label that is >= target.
+pub(crate) fn ceiling<'a, K, V, A, Q>(
+    tree: &'a AVLTree<K, V, A>,
+    target: &Q,
+) -> Option<&'a AVLNode<K, V, A>>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let mut best = None;
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        if *node.label.borrow() >= *target {
+            best = Some(node);
+            current = node.left.as_deref();
+        } else {
+            current = node.right.as_deref();
+        }
+    }
+    best
+}
+
+// How many labels in the tree are strictly less than target.
+pub(crate) fn rank<K, V, A, Q>(tree: &AVLTree<K, V, A>, target: &Q) -> usize
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let mut smaller = 0;
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        if *target <= *node.label.borrow() {
+            current = node.left.as_deref();
+        } else {
+            smaller += get_size(&node.left) + 1;
+            current = node.right.as_deref();
+        }
+    }
+    smaller
+}
+
+// The node that would be at position index if we listed the tree in order.
+pub(crate) fn select<K, V, A>(tree: &AVLTree<K, V, A>, index: usize) -> Option<&AVLNode<K, V, A>> {
+    let mut index = index;
+    let mut current = tree.as_deref();
+    while let Some(node) = current {
+        let left_size = get_size(&node.left);
+        match index.cmp(&left_size) {
+            Ordering::Less => current = node.left.as_deref(),
+            Ordering::Equal => return Some(node),
+            Ordering::Greater => {
+                index -= left_size + 1;
+                current = node.right.as_deref();
+            }
+        }
+    }
+    None
+}
+
+// Returns the removed label and value, or None if the tree never had target.
+pub(crate) fn delete<K, V, A, Q, P, O>(
+    tree: &mut Option<P>,
+    target: &Q,
+    observer: &mut O,
+) -> Option<(K, V)>
+where
+    K: Borrow<Q> + Ord,
+    A: Augment<K, V>,
+    Q: Ord + ?Sized,
+    P: Link<K, V, A>,
+    O: TreeObserver<K>,
+{
+    let node = tree.as_mut()?.make_mut();
+    let this_label: &Q = node.label.borrow();
+
+    let removed = if *target < *this_label {
+        delete(&mut node.left, target, observer)
+    } else if *target > *this_label {
+        delete(&mut node.right, target, observer)
+    } else {
+        // This is the node we want to delete. If either node is empty, we promote
+        // the other one, empty or not.
+        if node.left.as_ref().is_none() {
+            let right_child = node.right.take();
+            let this_one = mem::replace(tree, right_child).unwrap().into_inner();
+            Some((this_one.label, this_one.value))
+        } else if node.right.as_ref().is_none() {
+            let left_child = node.left.take();
+            let this_one = mem::replace(tree, left_child).unwrap().into_inner();
+            Some((this_one.label, this_one.value))
+        } else {
+            // If they are both non-empty, we get the
+            // minimum label from the right child.
+            let (new_label, new_value) = find_min_and_delete(&mut node.right, observer);
+            let old_label = mem::replace(&mut node.label, new_label);
+            let old_value = mem::replace(&mut node.value, new_value);
+            Some((old_label, old_value))
+        }
+    };
+    rebalance(tree, observer);
+    removed
+}
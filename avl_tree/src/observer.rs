@@ -0,0 +1,123 @@
+/// Which way a rotation turned. A left rotation lifts a node's right child
+/// into its place, and a right rotation lifts its left child.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    Left,
+    Right,
+}
+
+/// Hears about the work an `AvlMap` does to keep itself balanced. Every method
+/// does nothing by default, so an observer only needs the events it cares
+/// about, and `()`, which is what a map gets unless you ask for something
+/// else, compiles away to nothing.
+pub trait TreeObserver<K> {
+    /// `pivot` has just moved down a level and the child on the other side
+    /// has taken its place. A double rotation shows up as two of these.
+    fn rotated(&mut self, _rotation: Rotation, _pivot: &K) {}
+
+    /// The subtrees under `label` differ in height by two, so it is about to
+    /// be rotated. `balance_factor` is the left height minus the right one.
+    fn rebalancing(&mut self, _label: &K, _balance_factor: i64) {}
+
+    /// The height stored on `label` was stale and went from `old` to `new`.
+    fn height_fixed(&mut self, _label: &K, _old: i64, _new: i64) {}
+}
+
+impl<K> TreeObserver<K> for () {}
+
+/// One event as a `Vec` observer records it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeEvent<K> {
+    Rotated(Rotation, K),
+    Rebalancing(K, i64),
+    HeightFixed(K, i64, i64),
+}
+
+// Writing everything down in order is all most tests want, so a plain Vec will
+// do that without anyone having to write an observer of their own.
+impl<K: Clone> TreeObserver<K> for Vec<TreeEvent<K>> {
+    fn rotated(&mut self, rotation: Rotation, pivot: &K) {
+        self.push(TreeEvent::Rotated(rotation, pivot.clone()));
+    }
+
+    fn rebalancing(&mut self, label: &K, balance_factor: i64) {
+        self.push(TreeEvent::Rebalancing(label.clone(), balance_factor));
+    }
+
+    fn height_fixed(&mut self, label: &K, old: i64, new: i64) {
+        self.push(TreeEvent::HeightFixed(label.clone(), old, new));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::AvlMap;
+
+    type Recorded = AvlMap<i64, (), (), Vec<TreeEvent<i64>>>;
+
+    fn rotations(events: &[TreeEvent<i64>]) -> Vec<(Rotation, i64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                TreeEvent::Rotated(rotation, pivot) => Some((*rotation, *pivot)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_rotation_events() {
+        let mut map: Recorded = AvlMap::with_observer(vec![]);
+        map.insert(1, ());
+        map.insert(2, ());
+        assert_eq!(map.observer(), &vec![TreeEvent::HeightFixed(1, 1, 2)]);
+        map.observer_mut().clear();
+        map.insert(3, ());
+        assert_eq!(
+            map.observer(),
+            &vec![
+                TreeEvent::HeightFixed(2, 1, 2),
+                TreeEvent::HeightFixed(1, 2, 3),
+                TreeEvent::Rebalancing(1, -2),
+                TreeEvent::Rotated(Rotation::Left, 1),
+                TreeEvent::HeightFixed(1, 3, 1),
+            ]
+        );
+        map.validate();
+    }
+
+    #[test]
+    fn test_double_rotation_events() {
+        let mut map: Recorded = AvlMap::with_observer(vec![]);
+        for n in [3, 1, 2] {
+            map.insert(n, ());
+        }
+        assert_eq!(
+            rotations(map.observer()),
+            vec![(Rotation::Left, 1), (Rotation::Right, 3)]
+        );
+        assert_eq!(map.first(), Some((&1, &())));
+        assert_eq!(map.height(), 2);
+    }
+
+    #[test]
+    fn test_removes_and_lookups() {
+        let mut map: Recorded = AvlMap::with_observer(vec![]);
+        for n in 0..7 {
+            map.insert(n, ());
+        }
+        map.observer_mut().clear();
+        // Looking things up never changes the tree, so there is nothing to say.
+        assert!(map.contains_key(&3));
+        assert_eq!(map.get(&9), None);
+        assert!(map.observer().is_empty());
+        // 0..7 fills out a perfect tree rooted at 3; taking away 4, 5 and 6
+        // leaves the right side two shorter than the left.
+        for n in [4, 5, 6] {
+            map.remove(&n);
+        }
+        assert_eq!(rotations(map.observer()), vec![(Rotation::Right, 3)]);
+        map.validate();
+    }
+}
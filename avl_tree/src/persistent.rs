@@ -0,0 +1,302 @@
+// A map whose versions never change once made. Nodes live behind Arcs, and
+// insert and remove work on a copy of the root: Arc::make_mut copies a node
+// only if some other version can still see it, so each new version costs one
+// copied path from the root, O(log n) nodes, and shares everything else.
+//
+// Because make_mut hands out &mut just like Box does, Arc can be a node::Link
+// and the insert, delete and rotation code in node.rs works on these trees
+// unchanged. The price is that keys and values have to be Clone.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+use std::sync::Arc;
+
+use crate::check;
+use crate::iter::{walk_iterator, Parts, Walk};
+use crate::node::{self, AVLNode, Link, LinkKind};
+
+// Nodes whose children live behind Arcs.
+struct Shared;
+
+type PNode<K, V> = AVLNode<K, V, (), Shared>;
+
+type PTree<K, V> = Option<Arc<PNode<K, V>>>;
+
+impl<K, V, A> LinkKind<K, V, A> for Shared {
+    type Link = Arc<AVLNode<K, V, A, Shared>>;
+}
+
+impl<K: Clone, V: Clone, A: Clone> Link<K, V, A> for Arc<AVLNode<K, V, A, Shared>> {
+    type Kind = Shared;
+
+    fn new(node: AVLNode<K, V, A, Shared>) -> Self {
+        Arc::new(node)
+    }
+
+    fn make_mut(&mut self) -> &mut AVLNode<K, V, A, Shared> {
+        Arc::make_mut(self)
+    }
+
+    // Another version may still have this node, in which case we get a copy.
+    fn into_inner(self) -> AVLNode<K, V, A, Shared> {
+        Arc::unwrap_or_clone(self)
+    }
+}
+
+// A copy of one node for make_mut. The children are only Arcs, so they get
+// shared rather than copied.
+impl<K: Clone, V: Clone, A: Clone> Clone for AVLNode<K, V, A, Shared> {
+    fn clone(&self) -> Self {
+        AVLNode {
+            label: self.label.clone(),
+            value: self.value.clone(),
+            height: self.height,
+            size: self.size,
+            summary: self.summary.clone(),
+            left: self.left.clone(),
+            right: self.right.clone(),
+        }
+    }
+}
+
+/// An ordered map where `insert` and `remove` leave `self` alone and return a
+/// new version. Versions share every subtree they have in common, so keeping
+/// old ones around is cheap, and so is cloning one.
+#[derive(Debug)]
+pub struct PersistentMap<K, V> {
+    root: PTree<K, V>,
+}
+
+impl<K, V> PersistentMap<K, V> {
+    pub fn new() -> Self {
+        PersistentMap { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        node::get_size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> i64 {
+        node::get_height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            walk: Walk::new(self.root.as_deref()),
+            remaining: self.len(),
+        }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.label, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.label, &node.value))
+    }
+
+    // How many nodes this version has in common with other, by address.
+    #[cfg(test)]
+    fn shared_nodes(&self, other: &Self) -> usize {
+        let mut mine = vec![];
+        collect_nodes(&self.root, &mut mine);
+        let mut theirs = vec![];
+        collect_nodes(&other.root, &mut theirs);
+        mine.iter()
+            .filter(|node| theirs.iter().any(|other| Arc::ptr_eq(node, other)))
+            .count()
+    }
+}
+
+impl<K: Ord, V> PersistentMap<K, V> {
+    /// Panics if any node is out of order, out of balance or stale, the same
+    /// as AvlMap::validate.
+    pub fn validate(&self) {
+        if let Err(violation) = check::check(&self.root) {
+            panic!("{}", violation);
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            match key.cmp(node.label.borrow()) {
+                Ordering::Less => current = node.left.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => current = node.right.as_deref(),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    /// A new version with `key` set to `value`. If `key` was already there,
+    /// the old key is kept and only the value changes.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut root = self.root.clone();
+        node::insert(&mut root, key, value, &mut ());
+        PersistentMap { root }
+    }
+
+    /// A new version without `key`. If `key` was never there, the new version
+    /// shares the whole tree.
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut root = self.root.clone();
+        // Checking first saves copying a path for nothing.
+        if self.contains_key(key) {
+            node::delete(&mut root, key, &mut ());
+        }
+        PersistentMap { root }
+    }
+}
+
+// Cloning a version only bumps the root's reference count.
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentMap {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        PersistentMap::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for PersistentMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        // Nothing else can see these nodes yet, so make_mut never copies.
+        let mut root = None;
+        for (key, value) in iter {
+            node::insert(&mut root, key, value, &mut ());
+        }
+        PersistentMap { root }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a PersistentMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Borrowing iterator over the entries of a `PersistentMap`, in key order.
+pub struct Iter<'a, K, V> {
+    walk: Walk<&'a PNode<K, V>, (&'a K, &'a V)>,
+    remaining: usize,
+}
+
+walk_iterator!(Iter<'a, K, V>, (&'a K, &'a V), split_ref);
+
+fn split_ref<K, V>(node: &PNode<K, V>) -> Parts<&PNode<K, V>, (&K, &V)> {
+    (
+        node.left.as_deref(),
+        (&node.label, &node.value),
+        node.right.as_deref(),
+    )
+}
+
+#[cfg(test)]
+fn collect_nodes<K, V>(tree: &PTree<K, V>, found: &mut Vec<Arc<PNode<K, V>>>) {
+    if let Some(node) = tree {
+        found.push(node.clone());
+        collect_nodes(&node.left, found);
+        collect_nodes(&node.right, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_old_versions_stay_put() {
+        let empty: PersistentMap<i64, &str> = PersistentMap::new();
+        let one = empty.insert(1, "one");
+        let two = one.insert(2, "two");
+        let changed = two.insert(1, "uno");
+        let removed = changed.remove(&2);
+        assert!(empty.is_empty());
+        assert_eq!(one.iter().collect::<Vec<_>>(), vec![(&1, &"one")]);
+        assert_eq!(two.get(&1), Some(&"one"));
+        assert_eq!(changed.get(&1), Some(&"uno"));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed.first(), Some((&1, &"uno")));
+        assert_eq!(two.len(), 2);
+        assert_eq!(two.last(), Some((&2, &"two")));
+    }
+
+    #[test]
+    fn test_versions_share_structure() {
+        let big: PersistentMap<i64, i64> = (0..1000).map(|n| (n, n)).collect();
+        big.validate();
+        let bigger = big.insert(1000, 1000);
+        // Only the path down to the new key is new.
+        assert!(big.len() - big.shared_nodes(&bigger) <= big.height() as usize);
+        let smaller = big.remove(&500);
+        assert!(big.len() - big.shared_nodes(&smaller) <= 2 * big.height() as usize);
+        let same = big.remove(&5000);
+        assert_eq!(big.shared_nodes(&same), big.len());
+    }
+
+    #[test]
+    fn test_every_snapshot_matches_btreemap() {
+        let mut versions: Vec<PersistentMap<i64, i64>> = vec![PersistentMap::new()];
+        let mut models: Vec<BTreeMap<i64, i64>> = vec![BTreeMap::new()];
+        for n in 0..400 {
+            let key = (n * 7919) % 97;
+            let (version, model) = (versions.last().unwrap(), models.last().unwrap());
+            let (mut next, mut next_model) = (version.clone(), model.clone());
+            if n % 3 == 0 {
+                next = next.remove(&key);
+                next_model.remove(&key);
+            } else {
+                next = next.insert(key, n);
+                next_model.insert(key, n);
+            }
+            next.validate();
+            versions.push(next);
+            models.push(next_model);
+        }
+        for (version, model) in versions.iter().zip(models.iter()) {
+            assert!(version.iter().eq(model.iter()));
+            assert!(version.iter().rev().eq(model.iter().rev()));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Two ways to look at the shape of a tree: Graphviz for documents, and a
+// sideways drawing for the terminal. Both put each node's height and balance
+// factor next to its key, which is what you want to see when explaining a
+// rotation.
+
+use std::fmt::{Debug, Write};
+
+use crate::node::{balance_factor, AVLTree};
+
+pub(crate) fn to_dot<K: Debug, V, A>(tree: &AVLTree<K, V, A>) -> String {
+    let mut out =
+        String::from("digraph AvlTree {\n    node [shape=box, fontname=\"monospace\"];\n");
+    dot_subtree(tree, &mut out, &mut 0);
+    out.push_str("}\n");
+    out
+}
+
+// Writes the subtree out and returns the id it gave its root. Ids count up in
+// preorder, so they are stable for a given shape.
+fn dot_subtree<K: Debug, V, A>(
+    tree: &AVLTree<K, V, A>,
+    out: &mut String,
+    next_id: &mut usize,
+) -> Option<usize> {
+    let node = tree.as_ref()?;
+    let id = *next_id;
+    *next_id += 1;
+    let label = format!("{:?}", node.label)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    writeln!(
+        out,
+        "    n{} [label=\"{}\\nh={} bf={}\"];",
+        id,
+        label,
+        node.height,
+        balance_factor(tree)
+    )
+    .unwrap();
+    let left = dot_subtree(&node.left, out, next_id);
+    let right = dot_subtree(&node.right, out, next_id);
+    if left.is_none() && right.is_none() {
+        return Some(id);
+    }
+    // Graphviz lays children out in the order it meets the edges, so a lone
+    // child would end up centred. An invisible stand-in for the missing one
+    // keeps left children on the left.
+    for (side, child) in [("left", left), ("right", right)] {
+        match child {
+            Some(child) => writeln!(out, "    n{} -> n{};", id, child).unwrap(),
+            None => writeln!(
+                out,
+                "    n{0}_{1} [shape=point, style=invis];\n    n{0} -> n{0}_{1} [style=invis];",
+                id, side
+            )
+            .unwrap(),
+        }
+    }
+    Some(id)
+}
+
+// The tree on its side: the root at the left margin, bigger keys above and
+// smaller keys below, so reading from the bottom up goes in order.
+pub(crate) fn pretty<K: Debug, V, A>(tree: &AVLTree<K, V, A>) -> String {
+    let mut out = String::new();
+    draw(tree, &mut out, "", "", "");
+    out
+}
+
+// above, here and below are what goes in front of the lines for the right
+// subtree, this node, and the left subtree. The │ lines link each child back
+// to its parent.
+fn draw<K: Debug, V, A>(
+    tree: &AVLTree<K, V, A>,
+    out: &mut String,
+    above: &str,
+    here: &str,
+    below: &str,
+) {
+    let node = match tree {
+        Some(node) => node,
+        None => return,
+    };
+    draw(
+        &node.right,
+        out,
+        &format!("{}    ", above),
+        &format!("{}┌── ", above),
+        &format!("{}│   ", above),
+    );
+    writeln!(
+        out,
+        "{}{:?} (h={}, bf={})",
+        here,
+        node.label,
+        node.height,
+        balance_factor(tree)
+    )
+    .unwrap();
+    draw(
+        &node.left,
+        out,
+        &format!("{}│   ", below),
+        &format!("{}└── ", below),
+        &format!("{}    ", below),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::AvlMap;
+
+    fn one_to_five() -> AvlMap<i64, ()> {
+        let mut map = AvlMap::new();
+        for n in 1..=5 {
+            map.insert(n, ());
+        }
+        map
+    }
+
+    #[test]
+    fn test_pretty() {
+        assert_eq!(
+            one_to_five().pretty(),
+            concat!(
+                "    ┌── 5 (h=1, bf=0)\n",
+                "┌── 4 (h=2, bf=0)\n",
+                "│   └── 3 (h=1, bf=0)\n",
+                "2 (h=3, bf=-1)\n",
+                "└── 1 (h=1, bf=0)\n",
+            )
+        );
+        assert_eq!(AvlMap::<i64, ()>::new().pretty(), "");
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = one_to_five().to_dot();
+        assert!(dot.starts_with("digraph AvlTree {\n"));
+        assert!(dot.contains("    n0 [label=\"2\\nh=3 bf=-1\"];\n"));
+        assert!(dot.contains("    n0 -> n1;\n    n0 -> n2;\n"));
+        assert!(dot.contains("    n2 [label=\"4\\nh=2 bf=0\"];\n"));
+        assert!(!dot.contains("invis"));
+
+        let mut lopsided: AvlMap<&str, ()> = AvlMap::new();
+        lopsided.insert("a\"b", ());
+        lopsided.insert("c", ());
+        let dot = lopsided.to_dot();
+        assert!(dot.contains("[label=\"\\\"a\\\\\\\"b\\\"\\nh=2 bf=-1\"];"));
+        assert!(dot.contains("    n0 -> n0_left [style=invis];\n    n0 -> n1;\n"));
+    }
+}
@@ -0,0 +1,153 @@
+// With the serde feature on, maps and sets are written out as a plain sequence
+// in key order: [key, value] pairs for a map, bare keys for a set. Reading one
+// back builds the tree in O(n) with from_sorted_iter, so we insist on what
+// that needs, keys in strictly increasing order, and say so with an error
+// rather than a panic when the input does not have it.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, Error, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::augment::Augment;
+use crate::map::AvlMap;
+use crate::set::AvlSet;
+
+impl<K: Serialize, V: Serialize, A, O> Serialize for AvlMap<K, V, A, O> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<K: Serialize> Serialize for AvlSet<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, K, V, A, O> Deserialize<'de> for AvlMap<K, V, A, O>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+    A: Augment<K, V>,
+    O: Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = deserializer.deserialize_seq(SortedVisitor {
+            in_order: |before: &(K, V), after: &(K, V)| before.0 < after.0,
+            marker: PhantomData,
+        })?;
+        Ok(AvlMap::from_sorted_iter(entries))
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord> Deserialize<'de> for AvlSet<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let keys = deserializer.deserialize_seq(SortedVisitor {
+            in_order: |before: &K, after: &K| before < after,
+            marker: PhantomData,
+        })?;
+        Ok(AvlSet::from_sorted_iter(keys))
+    }
+}
+
+// Reads a sequence into a Vec, checking each item against the one before it
+// as it goes.
+struct SortedVisitor<T> {
+    in_order: fn(&T, &T) -> bool,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SortedVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence in strictly increasing key order")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Vec<T>, S::Error> {
+        // Don't trust the size hint too far; it comes from the input.
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(item) = seq.next_element()? {
+            if let Some(before) = items.last() {
+                if !(self.in_order)(before, &item) {
+                    return Err(S::Error::custom(format_args!(
+                        "item {} is not greater than the one before it; \
+                         keys must be strictly increasing",
+                        items.len()
+                    )));
+                }
+            }
+            items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::Error as ValueError;
+    use serde::de::IntoDeserializer;
+    use serde_test::{assert_ser_tokens, Token};
+
+    // Reads a T back out of a Vec, the way a format would hand us a sequence.
+    fn from_vec<T, U>(items: Vec<U>) -> Result<T, ValueError>
+    where
+        T: for<'de> Deserialize<'de>,
+        U: for<'de> IntoDeserializer<'de, ValueError>,
+    {
+        T::deserialize(items.into_deserializer())
+    }
+
+    #[test]
+    fn test_serialize() {
+        let map: AvlMap<i64, char> = [(3, 'c'), (1, 'a'), (2, 'b')].into_iter().collect();
+        let mut tokens = vec![Token::Seq { len: Some(3) }];
+        for (key, value) in [(1, 'a'), (2, 'b'), (3, 'c')] {
+            tokens.extend([
+                Token::Tuple { len: 2 },
+                Token::I64(key),
+                Token::Char(value),
+                Token::TupleEnd,
+            ]);
+        }
+        tokens.push(Token::SeqEnd);
+        assert_ser_tokens(&map, &tokens);
+
+        let set: AvlSet<&str> = ["b", "a"].into_iter().collect();
+        assert_ser_tokens(
+            &set,
+            &[
+                Token::Seq { len: Some(2) },
+                Token::Str("a"),
+                Token::Str("b"),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_deserialize_builds_balanced() {
+        let set: AvlSet<i64> = from_vec((0..100).collect()).unwrap();
+        set.validate();
+        assert_eq!(set.len(), 100);
+        assert_eq!(set.height(), 7);
+
+        let pairs: Vec<Vec<i64>> = (0..10).map(|n| vec![n, n * n]).collect();
+        let map: AvlMap<i64, i64> = from_vec(pairs).unwrap();
+        map.validate();
+        assert_eq!(map.get(&9), Some(&81));
+        assert!(from_vec::<AvlSet<i64>, i64>(vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_unsorted_and_duplicates() {
+        let unsorted = from_vec::<AvlSet<i64>, i64>(vec![1, 3, 2]).unwrap_err();
+        assert!(unsorted.to_string().starts_with("item 2 is not greater"));
+        let duplicate =
+            from_vec::<AvlMap<i64, i64>, Vec<i64>>(vec![vec![1, 10], vec![1, 20]]).unwrap_err();
+        assert!(duplicate.to_string().starts_with("item 1 is not greater"));
+    }
+}
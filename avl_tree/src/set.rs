@@ -0,0 +1,409 @@
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+
+use crate::check::Violation;
+use crate::map::{self, AvlMap};
+
+/// An ordered set backed by an AVL tree.
+#[derive(Debug, Default)]
+pub struct AvlSet<K> {
+    map: AvlMap<K, ()>,
+}
+
+impl<K> AvlSet<K> {
+    pub fn new() -> Self {
+        AvlSet { map: AvlMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn height(&self) -> i64 {
+        self.map.height()
+    }
+
+    /// Also checks that the keys are in order and every size is up to date.
+    pub fn check(&self) -> Result<(), Violation>
+    where
+        K: Ord,
+    {
+        self.map.check()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            inner: self.map.keys(),
+        }
+    }
+
+    /// Iterates over the keys that fall in `range`, in order.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range {
+            inner: self.map.range(range),
+        }
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        self.map.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        self.map.last().map(|(key, _)| key)
+    }
+
+    /// The greatest key less than or equal to `key`.
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.floor(key).map(|(key, _)| key)
+    }
+
+    /// The smallest key greater than or equal to `key`.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.ceiling(key).map(|(key, _)| key)
+    }
+
+    /// The number of keys strictly less than `key`.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.rank(key)
+    }
+
+    /// The `index`-th smallest key, counting from zero.
+    pub fn select(&self, index: usize) -> Option<&K> {
+        self.map.select(index).map(|(key, _)| key)
+    }
+}
+
+impl<K: Debug> AvlSet<K> {
+    /// The tree in Graphviz's dot language; see `AvlMap::to_dot`.
+    pub fn to_dot(&self) -> String {
+        self.map.to_dot()
+    }
+
+    /// The tree drawn on its side; see `AvlMap::pretty`.
+    pub fn pretty(&self) -> String {
+        self.map.pretty()
+    }
+
+    pub fn pretty_print(&self) {
+        self.map.pretty_print();
+    }
+}
+
+impl<K: Ord> AvlSet<K> {
+    /// Panics if any node is out of order, out of balance or stale.
+    pub fn validate(&self) {
+        self.map.validate();
+    }
+
+    /// Returns false if an equal key was already in the set.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    /// Returns false if no equal key was in the set.
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(key).is_some()
+    }
+
+    pub fn take<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove_entry(key).map(|(key, _)| key)
+    }
+
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.map.pop_first().map(|(key, _)| key)
+    }
+
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.map.pop_last().map(|(key, _)| key)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.get_key_value(key).map(|(key, _)| key)
+    }
+
+    /// Builds a set from `left`, `key` and `right` in O(log n). Panics unless
+    /// every key in `left` is less than `key` and every key in `right` is
+    /// greater.
+    pub fn join(left: Self, key: K, right: Self) -> Self {
+        AvlSet {
+            map: AvlMap::join(left.map, key, (), right.map),
+        }
+    }
+
+    /// Afterwards `self` holds the keys less than `key` and the returned set
+    /// holds the rest.
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        AvlSet {
+            map: self.map.split_off(key),
+        }
+    }
+
+    /// Moves every key of `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.map.append(&mut other.map);
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        AvlSet {
+            map: self.map.union(other.map),
+        }
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        AvlSet {
+            map: self.map.intersection(other.map),
+        }
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        AvlSet {
+            map: self.map.difference(other.map),
+        }
+    }
+}
+
+impl<K: Ord> AvlSet<K> {
+    /// Builds a perfectly balanced set in O(n) from keys that are already in
+    /// strictly increasing order. Panics if they are not.
+    pub fn from_sorted_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        AvlSet {
+            map: AvlMap::from_sorted_iter(iter.into_iter().map(|key| (key, ()))),
+        }
+    }
+}
+
+impl<K: Ord> FromIterator<K> for AvlSet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        AvlSet {
+            map: iter.into_iter().map(|key| (key, ())).collect(),
+        }
+    }
+}
+
+impl<K: Ord> Extend<K> for AvlSet<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|key| (key, ())));
+    }
+}
+
+/// Borrowing iterator over an `AvlSet`, in order.
+pub struct Iter<'a, K> {
+    inner: map::Keys<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for Iter<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K> ExactSizeIterator for Iter<'_, K> {}
+
+impl<K> FusedIterator for Iter<'_, K> {}
+
+/// Borrowing iterator over the keys of an `AvlSet` that fall in a range.
+pub struct Range<'a, K> {
+    inner: map::Range<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Range<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+impl<K> DoubleEndedIterator for Range<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K> FusedIterator for Range<'_, K> {}
+
+/// Owning iterator over an `AvlSet`, in order.
+pub struct IntoIter<K> {
+    inner: map::IntoIter<K, ()>,
+}
+
+impl<K> Iterator for IntoIter<K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for IntoIter<K> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K> ExactSizeIterator for IntoIter<K> {}
+
+impl<K> FusedIterator for IntoIter<K> {}
+
+impl<K> IntoIterator for AvlSet<K> {
+    type Item = K;
+    type IntoIter = IntoIter<K>;
+
+    fn into_iter(self) -> IntoIter<K> {
+        IntoIter {
+            inner: self.map.into_iter(),
+        }
+    }
+}
+
+impl<'a, K> IntoIterator for &'a AvlSet<K> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Iter<'a, K> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Bound::{Excluded, Included};
+
+    #[test]
+    fn test_insert_twice() {
+        let mut set: AvlSet<char> = AvlSet::new();
+        assert!(set.insert('a'));
+        assert!(!set.insert('a'));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&'a'));
+        assert!(!set.contains(&'z'));
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut set: AvlSet<i64> = AvlSet::new();
+        for n in [5, 3, 8, 1, 4, 7, 9, 2, 6] {
+            set.insert(n);
+        }
+        assert_eq!(
+            set.iter().copied().collect::<Vec<i64>>(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9]
+        );
+        assert!(set.remove(&5));
+        assert!(!set.remove(&5));
+        set.validate();
+        assert_eq!(
+            set.into_iter().rev().collect::<Vec<i64>>(),
+            vec![9, 8, 7, 6, 4, 3, 2, 1]
+        );
+    }
+
+    #[test]
+    fn test_range_and_neighbours() {
+        let mut set: AvlSet<String> = AvlSet::new();
+        for word in ["delta", "alpha", "echo", "charlie", "bravo"] {
+            set.insert(word.to_string());
+        }
+        let middle: Vec<&str> = set
+            .range::<str, _>((Included("b"), Excluded("d")))
+            .map(|w| w.as_str())
+            .collect();
+        assert_eq!(middle, vec!["bravo", "charlie"]);
+        assert_eq!(set.floor("cat").map(|w| w.as_str()), Some("bravo"));
+        assert_eq!(set.ceiling("cat").map(|w| w.as_str()), Some("charlie"));
+        assert_eq!(set.pop_first().as_deref(), Some("alpha"));
+        assert_eq!(set.pop_last().as_deref(), Some("echo"));
+        assert_eq!(set.first().map(|w| w.as_str()), Some("bravo"));
+        assert_eq!(set.last().map(|w| w.as_str()), Some("delta"));
+        set.validate();
+    }
+
+    #[test]
+    fn test_bulk_operations() {
+        let mut low: AvlSet<i64> = AvlSet::new();
+        let mut high: AvlSet<i64> = AvlSet::new();
+        for n in 0..50 {
+            low.insert(n);
+            high.insert(n + 25);
+        }
+        high.remove(&39);
+        let both = low.intersection(high.split_off(&40));
+        both.validate();
+        assert_eq!(both.len(), 10);
+        assert_eq!(high.len(), 14);
+        let joined = AvlSet::join(high, 39, both);
+        joined.validate();
+        assert_eq!(joined.first(), Some(&25));
+        assert_eq!(joined.select(14), Some(&39));
+        assert_eq!(joined.select(15), Some(&40));
+        assert_eq!(joined.len(), 14 + 1 + 10);
+    }
+}
@@ -0,0 +1,121 @@
+// Compares dijkstra with bidirectional_dijkstra on a big generated grid, a
+// rough stand-in for a road network. What we care about is how many nodes each
+// one has to expand, which we count by wrapping the graphs; the times are
+// there too. Like the benches in avl_tree, this is a plain program: run it with
+// `cargo bench` and read the table.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use shortest_path::{bidirectional_dijkstra, find_path, from_fn, Edge, SearchOutcome};
+
+const SIDE: u32 = 400;
+const QUERIES: usize = 8;
+
+type Node = (u32, u32);
+
+// Each direction of each street gets its own weight from 1 to 9, so the
+// graph is directed and its reverse is a different graph.
+fn weight(from: Node, to: Node) -> u64 {
+    let mut hash =
+        (from.0 as u64) << 48 | (from.1 as u64) << 32 | (to.0 as u64) << 16 | to.1 as u64;
+    hash = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    hash ^= hash >> 29;
+    1 + hash % 9
+}
+
+fn streets((x, y): Node) -> impl Iterator<Item = Node> {
+    [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .map(move |(dx, dy): (i64, i64)| (x as i64 + dx, y as i64 + dy))
+        .filter(|&(x, y)| (0..SIDE as i64).contains(&x) && (0..SIDE as i64).contains(&y))
+        .map(|(x, y)| (x as u32, y as u32))
+}
+
+// Random pairs of nodes, far enough apart to be interesting.
+fn queries() -> Vec<(Node, Node)> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % SIDE as u64) as u32
+    };
+    let mut pairs = vec![];
+    while pairs.len() < QUERIES {
+        let (a, b) = ((random(), random()), (random(), random()));
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) > SIDE / 2 {
+            pairs.push((a, b));
+        }
+    }
+    pairs
+}
+
+fn main() {
+    let expanded = Cell::new(0usize);
+    let forward = from_fn(|&node: &Node| {
+        expanded.set(expanded.get() + 1);
+        streets(node).map(move |dest| Edge {
+            dest,
+            w: weight(node, dest),
+        })
+    });
+    let backward = from_fn(|&node: &Node| {
+        expanded.set(expanded.get() + 1);
+        streets(node).map(move |dest| Edge {
+            dest,
+            w: weight(dest, node),
+        })
+    });
+
+    println!(
+        "{}x{} grid, {} nodes, nodes expanded and ms per query",
+        SIDE,
+        SIDE,
+        SIDE * SIDE
+    );
+    println!(
+        "{:<24} {:>6} {:>10} {:>10} {:>8} {:>8}",
+        "query", "cost", "one-sided", "two-sided", "one ms", "two ms"
+    );
+    let mut totals = [0usize; 2];
+    let mut times = [Duration::ZERO; 2];
+    for (source, target) in queries() {
+        expanded.set(0);
+        let start = Instant::now();
+        let one = find_path(&forward, source, |&node| node == target, None).unwrap();
+        let one_time = start.elapsed();
+        let one_expanded = expanded.replace(0);
+
+        let start = Instant::now();
+        let two = bidirectional_dijkstra(&forward, &backward, source, target).unwrap();
+        let two_time = start.elapsed();
+        let two_expanded = expanded.get();
+
+        let cost = match (one, two) {
+            (SearchOutcome::Found { cost: a, .. }, SearchOutcome::Found { cost: b, .. }) => {
+                assert_eq!(a, b, "the two searches disagree");
+                a
+            }
+            other => panic!("every pair on the grid is connected, got {:?}", other),
+        };
+        println!(
+            "{:<24} {:>6} {:>10} {:>10} {:>8.1} {:>8.1}",
+            format!("{:?} -> {:?}", source, target),
+            cost,
+            one_expanded,
+            two_expanded,
+            one_time.as_secs_f64() * 1000.0,
+            two_time.as_secs_f64() * 1000.0
+        );
+        totals[0] += one_expanded;
+        totals[1] += two_expanded;
+        times[0] += one_time;
+        times[1] += two_time;
+    }
+    println!(
+        "two-sided expanded {:.0}% as many nodes in {:.0}% of the time",
+        100.0 * totals[1] as f64 / totals[0] as f64,
+        100.0 * times[1].as_secs_f64() / times[0].as_secs_f64()
+    );
+}
@@ -0,0 +1,409 @@
+// Distances between every pair of nodes in a graph over 0..len. Floyd-Warshall
+// works on a flat len * len matrix and is the one to use for small or dense
+// graphs. Johnson's algorithm runs dijkstra from every node, which is quicker
+// when there are far fewer edges than pairs; negative weights are fine for it
+// too, after bellman_ford works out how to reweight the edges so that none are.
+
+use crate::bellman_ford::bellman_ford;
+use crate::dijkstra::{dijkstra, Predecessors};
+use crate::error::SearchError;
+use crate::graph::{from_fn, Edge, Graph};
+use crate::weight::{Subtract, Weight};
+
+/// The answer to an all-pairs query: the distance between any two nodes, and
+/// the first step of a shortest path between them so the path itself can be
+/// pieced together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceMatrix<W> {
+    len: usize,
+    // Row-major, like AdjacencyMatrix: from i to j is at i * len + j.
+    distances: Vec<Option<W>>,
+    next: Vec<Option<usize>>,
+}
+
+impl<W> DistanceMatrix<W> {
+    // Every node is zero away from itself and nothing else is reachable yet.
+    fn new(len: usize) -> Self
+    where
+        W: Weight,
+    {
+        let mut distances = vec![None; len * len];
+        for i in 0..len {
+            distances[i * len + i] = Some(W::zero());
+        }
+        DistanceMatrix {
+            len,
+            distances,
+            next: vec![None; len * len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The length of the shortest path from `from` to `to`, or None if there
+    /// is no path.
+    pub fn distance(&self, from: usize, to: usize) -> Option<&W> {
+        self.distances[self.index(from, to)].as_ref()
+    }
+
+    /// The node after `from` on a shortest path to `to`. None if there is no
+    /// path, or if `from` and `to` are the same node.
+    pub fn next_hop(&self, from: usize, to: usize) -> Option<usize> {
+        self.next[self.index(from, to)]
+    }
+
+    /// A shortest path from `from` to `to`, both ends included, like
+    /// `traceback` gives you. None if there is no path.
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        self.distance(from, to)?;
+        let mut path = vec![from];
+        let mut current = from;
+        while current != to {
+            current = self.next_hop(current, to)?;
+            path.push(current);
+        }
+        Some(path)
+    }
+
+    fn index(&self, from: usize, to: usize) -> usize {
+        assert!(
+            from < self.len && to < self.len,
+            "pair {} -> {} is outside a {} node matrix",
+            from,
+            to,
+            self.len
+        );
+        from * self.len + to
+    }
+}
+
+/// All-pairs shortest paths for a graph whose nodes are `0..len`, by
+/// Floyd-Warshall. Takes O(len³) time whatever the edges are. Negative weights
+/// are allowed, but a negative cycle is an error.
+///
+/// # Panics
+///
+/// If the graph has an edge to a node outside `0..len`.
+pub fn floyd_warshall<G, W>(graph: &G, len: usize) -> Result<DistanceMatrix<W>, SearchError<usize>>
+where
+    G: Graph<Node = usize, Weight = W>,
+    W: Weight,
+{
+    let mut matrix = DistanceMatrix::new(len);
+    for from in 0..len {
+        for edge in graph.neighbors(&from) {
+            assert!(
+                edge.dest < len,
+                "floyd_warshall: edge {} -> {} is outside a {} node graph",
+                from,
+                edge.dest,
+                len
+            );
+            let index = matrix.index(from, edge.dest);
+            if from == edge.dest {
+                if edge.w < W::zero() {
+                    return Err(SearchError::NegativeCycle(vec![from, from]));
+                }
+                continue;
+            }
+            // If there are parallel edges, the cheapest one wins.
+            if matrix.distances[index]
+                .as_ref()
+                .is_none_or(|distance| edge.w < *distance)
+            {
+                matrix.distances[index] = Some(edge.w);
+                matrix.next[index] = Some(edge.dest);
+            }
+        }
+    }
+
+    for k in 0..len {
+        for i in 0..len {
+            let to_k = match &matrix.distances[i * len + k] {
+                Some(distance) => distance.clone(),
+                None => continue,
+            };
+            for j in 0..len {
+                let from_k = match &matrix.distances[k * len + j] {
+                    Some(distance) => distance,
+                    None => continue,
+                };
+                let alt = to_k
+                    .checked_add(from_k)
+                    .ok_or(SearchError::Overflow { from: k, to: j })?;
+                if matrix.distances[i * len + j]
+                    .as_ref()
+                    .is_some_and(|distance| alt >= *distance)
+                {
+                    continue;
+                }
+                if i == j {
+                    // This is the first time any node has got back to itself
+                    // for less than nothing, so the paths through k are still
+                    // good and make up the cycle.
+                    let mut cycle = matrix.path(i, k).unwrap();
+                    cycle.extend(matrix.path(k, i).unwrap().into_iter().skip(1));
+                    return Err(SearchError::NegativeCycle(cycle));
+                }
+                matrix.distances[i * len + j] = Some(alt);
+                matrix.next[i * len + j] = matrix.next[i * len + k];
+            }
+        }
+    }
+    Ok(matrix)
+}
+
+/// All-pairs shortest paths for a graph whose nodes are `0..len`, by Johnson's
+/// algorithm: one `bellman_ford` to get rid of negative weights, then a
+/// `dijkstra` from every node. Better than `floyd_warshall` for sparse graphs.
+/// A negative cycle is an error.
+///
+/// # Panics
+///
+/// If the graph has an edge to a node outside `0..len`.
+pub fn johnson<G, W>(graph: &G, len: usize) -> Result<DistanceMatrix<W>, SearchError<usize>>
+where
+    G: Graph<Node = usize, Weight = W>,
+    W: Subtract,
+{
+    // The potential of each node is its distance from an extra node with a
+    // zero-weight edge to every other one, so it is never more than zero.
+    // Every node is reachable from the extra one, so bellman_ford goes over
+    // every edge and WithExtraSource gets to check each of them is in range.
+    let potentials: Vec<W> = {
+        let extended = WithExtraSource { graph, len };
+        let distances = bellman_ford(&extended, None).map_err(|error| match error {
+            SearchError::NegativeCycle(cycle) => {
+                SearchError::NegativeCycle(cycle.into_iter().flatten().collect())
+            }
+            SearchError::Overflow { from, to } => SearchError::Overflow {
+                from: from.expect("nothing is added on the way out of the extra node"),
+                to: to.unwrap(),
+            },
+        })?;
+        (0..len)
+            .map(|node| distances[&Some(node)].0.clone())
+            .collect()
+    };
+
+    // Adding the potential at the start of an edge and taking away the one at
+    // the end makes every weight at least zero, and changes the length of any
+    // path from a to b by the same amount, so shortest paths stay shortest.
+    let mut reweighted: Vec<Vec<Edge<usize, W>>> = Vec::with_capacity(len);
+    for from in 0..len {
+        let mut edges = vec![];
+        for edge in graph.neighbors(&from) {
+            let w = edge
+                .w
+                .checked_add(&potentials[from])
+                .and_then(|w| w.checked_sub(&potentials[edge.dest]))
+                .ok_or(SearchError::Overflow {
+                    from,
+                    to: edge.dest,
+                })?;
+            edges.push(Edge { dest: edge.dest, w });
+        }
+        reweighted.push(edges);
+    }
+    let reweighted = &reweighted;
+    let reweighted = from_fn(move |node: &usize| reweighted[*node].iter().cloned());
+
+    let mut matrix = DistanceMatrix::new(len);
+    for from in 0..len {
+        let (_, distances) = dijkstra(&reweighted, from, |_| false)?;
+        for (&to, (distance, _pred)) in distances.iter() {
+            let distance = distance
+                .checked_add(&potentials[to])
+                .and_then(|distance| distance.checked_sub(&potentials[from]))
+                .ok_or(SearchError::Overflow { from, to })?;
+            let index = matrix.index(from, to);
+            matrix.distances[index] = Some(distance);
+            matrix.next[index] = Some(first_hop(&distances, from, to));
+        }
+    }
+    Ok(matrix)
+}
+
+// The node after `source` on the way to `dest`, going by the predecessors.
+fn first_hop<W>(predecessors: &Predecessors<usize, W>, source: usize, dest: usize) -> usize {
+    let mut current = dest;
+    loop {
+        let pred = predecessors[&current].1;
+        if pred == source {
+            return current;
+        }
+        current = pred;
+    }
+}
+
+// A graph with one more node, None, that has a zero-weight edge to every node
+// of the one inside it.
+struct WithExtraSource<'a, G> {
+    graph: &'a G,
+    len: usize,
+}
+
+impl<G: Graph<Node = usize>> Graph for WithExtraSource<'_, G> {
+    type Node = Option<usize>;
+    type Weight = G::Weight;
+
+    fn neighbors(
+        &self,
+        node: &Option<usize>,
+    ) -> impl Iterator<Item = Edge<Option<usize>, G::Weight>> {
+        let extra = node.is_none().then_some(0..self.len).into_iter().flatten();
+        let len = self.len;
+        let inside = node.iter().flat_map(move |from| {
+            self.graph.neighbors(from).inspect(move |edge| {
+                assert!(
+                    edge.dest < len,
+                    "johnson: edge {} -> {} is outside a {} node graph",
+                    from,
+                    edge.dest,
+                    len
+                );
+            })
+        });
+        extra
+            .map(|dest| Edge {
+                dest: Some(dest),
+                w: G::Weight::zero(),
+            })
+            .chain(inside.map(|edge| Edge {
+                dest: Some(edge.dest),
+                w: edge.w,
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::AdjacencyMatrix;
+
+    fn example() -> AdjacencyMatrix<i64> {
+        let mut graph = AdjacencyMatrix::new(5);
+        graph.set_edge(0, 1, 3);
+        graph.set_edge(0, 2, 8);
+        graph.set_edge(1, 3, 1);
+        graph.set_edge(2, 1, 4);
+        graph.set_edge(3, 0, 2);
+        graph.set_edge(3, 2, -5);
+        graph
+    }
+
+    #[test]
+    fn test_floyd_warshall() {
+        let matrix = floyd_warshall(&example(), 5).unwrap();
+        assert_eq!(matrix.distance(0, 2), Some(&-1));
+        assert_eq!(matrix.path(0, 2), Some(vec![0, 1, 3, 2]));
+        assert_eq!(matrix.distance(2, 0), Some(&7));
+        assert_eq!(matrix.next_hop(2, 0), Some(1));
+        assert_eq!(matrix.path(3, 3), Some(vec![3]));
+        assert_eq!(matrix.next_hop(3, 3), None);
+        // Node 4 has no edges at all.
+        assert_eq!(matrix.distance(0, 4), None);
+        assert_eq!(matrix.path(4, 0), None);
+    }
+
+    #[test]
+    fn test_johnson_agrees() {
+        assert_eq!(
+            johnson(&example(), 5).unwrap().distances,
+            floyd_warshall(&example(), 5).unwrap().distances
+        );
+    }
+
+    // Told there are only 3 nodes, the edge 1 -> 3 goes nowhere.
+    #[test]
+    #[should_panic(expected = "floyd_warshall: edge 1 -> 3 is outside a 3 node graph")]
+    fn test_floyd_warshall_edge_out_of_range() {
+        let _ = floyd_warshall(&example(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "johnson: edge 1 -> 3 is outside a 3 node graph")]
+    fn test_johnson_edge_out_of_range() {
+        let _ = johnson(&example(), 3);
+    }
+
+    #[test]
+    fn test_negative_cycles() {
+        let mut graph = example();
+        graph.set_edge(2, 1, 3);
+        for result in [floyd_warshall(&graph, 5), johnson(&graph, 5)] {
+            let error = result.unwrap_err();
+            let SearchError::NegativeCycle(cycle) = error else {
+                panic!("expected a negative cycle, got {:?}", error);
+            };
+            assert_eq!(cycle.first(), cycle.last());
+            let cost: i64 = cycle
+                .windows(2)
+                .map(|pair| graph.weight(pair[0], pair[1]).unwrap())
+                .sum();
+            assert!(cost < 0, "{:?} costs {}", cycle, cost);
+        }
+
+        let mut graph = AdjacencyMatrix::new(2);
+        graph.set_edge(1, 1, -1i64);
+        assert_eq!(
+            floyd_warshall(&graph, 2),
+            Err(SearchError::NegativeCycle(vec![1, 1]))
+        );
+    }
+
+    #[test]
+    fn test_random_graphs() {
+        // Negative weights made by taking a node's number away from the
+        // edges into it and adding it back on the edges out, which can't
+        // make a negative cycle. Checks both methods against dijkstra on the
+        // original weights.
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        let mut random = move |below: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % below
+        };
+        for _ in 0..20 {
+            let len = 1 + random(12) as usize;
+            let mut positive: AdjacencyMatrix<i64> = AdjacencyMatrix::new(len);
+            let mut shifted: AdjacencyMatrix<i64> = AdjacencyMatrix::new(len);
+            for _ in 0..random(40) {
+                let (from, to) = (random(len as u64) as usize, random(len as u64) as usize);
+                let w = random(10) as i64;
+                positive.set_edge(from, to, w);
+                shifted.set_edge(from, to, w + from as i64 - to as i64);
+            }
+            let by_floyd = floyd_warshall(&shifted, len).unwrap();
+            let by_johnson = johnson(&shifted, len).unwrap();
+            for from in 0..len {
+                let (_, distances) = dijkstra(&positive, from, |_| false).unwrap();
+                for to in 0..len {
+                    let expected = match distances.get(&to) {
+                        _ if from == to => Some(0),
+                        Some((distance, _pred)) => Some(distance + from as i64 - to as i64),
+                        None => None,
+                    };
+                    for matrix in [&by_floyd, &by_johnson] {
+                        assert_eq!(matrix.distance(from, to).copied(), expected);
+                        let Some(path) = matrix.path(from, to) else {
+                            continue;
+                        };
+                        let cost: i64 = path
+                            .windows(2)
+                            .map(|pair| shifted.weight(pair[0], pair[1]).unwrap())
+                            .sum();
+                        assert_eq!(Some(cost), expected);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+// Bellman-Ford in its queue-based form (sometimes called SPFA): only nodes
+// whose distance just went down get looked at again. Unlike dijkstra it copes
+// with negative weights, at the cost of maybe visiting nodes many times.
+//
+// A negative cycle makes distances go down forever, so we need a way to notice
+// one. We remember how many edges each node's best route has. A route with at
+// least as many edges as there are nodes seen so far has to repeat a node, and
+// the only way such a route can be the best is by going round a negative
+// cycle. That is when we go looking for the cycle among the predecessors.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use crate::dijkstra::Predecessors;
+use crate::error::SearchError;
+use crate::graph::Graph;
+use crate::weight::Weight;
+
+/// Finds the shortest route from `source` to every node it can reach, with
+/// negative weights allowed. Returns the same predecessors as `dijkstra`, so
+/// `traceback` works on them. If a negative cycle can be reached from `source`
+/// there are no shortest routes, and you get `SearchError::NegativeCycle`
+/// with the cycle in it instead.
+pub fn bellman_ford<G, T, W>(graph: &G, source: T) -> Result<Predecessors<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Hash + Clone,
+    W: Weight,
+{
+    // Distance and number of edges of the best route to each node, source
+    // included. The predecessors leave the source out so traceback stops.
+    let mut best: HashMap<T, (W, usize)> = HashMap::from([(source.clone(), (W::zero(), 0))]);
+    let mut predecessors: Predecessors<T, W> = Default::default();
+    let mut queue: VecDeque<T> = VecDeque::from([source.clone()]);
+    let mut queued: HashSet<T> = HashSet::from([source.clone()]);
+
+    while let Some(current) = queue.pop_front() {
+        queued.remove(&current);
+        let (so_far, edges) = best[&current].clone();
+        for neighbor in graph.neighbors(&current) {
+            let alt: W = so_far
+                .checked_add(&neighbor.w)
+                .ok_or_else(|| SearchError::Overflow {
+                    from: current.clone(),
+                    to: neighbor.dest.clone(),
+                })?;
+            let is_better: bool = match best.get(&neighbor.dest) {
+                Some((distance, _edges)) => alt < *distance,
+                None => true,
+            };
+            if !is_better {
+                continue;
+            }
+            best.insert(neighbor.dest.clone(), (alt.clone(), edges + 1));
+            predecessors.insert(neighbor.dest.clone(), (alt, current.clone()));
+            // Getting back to the source for less than nothing is a negative
+            // cycle straight away.
+            if neighbor.dest == source || edges + 1 >= best.len() {
+                if let Some(cycle) = find_cycle(&predecessors, neighbor.dest.clone()) {
+                    return Err(SearchError::NegativeCycle(cycle));
+                }
+            }
+            if queued.insert(neighbor.dest.clone()) {
+                queue.push_back(neighbor.dest);
+            }
+        }
+    }
+    Ok(predecessors)
+}
+
+// Follows predecessors back from `start`. If that goes round in a circle,
+// returns the circle in the order you would walk it, with the first node
+// repeated at the end. If it gets back to the source instead, returns None.
+fn find_cycle<T: Eq + Hash + Clone, W>(
+    predecessors: &Predecessors<T, W>,
+    start: T,
+) -> Option<Vec<T>> {
+    let mut walked: Vec<T> = vec![];
+    let mut positions: HashMap<T, usize> = HashMap::new();
+    let mut current = start;
+    loop {
+        if let Some(&position) = positions.get(&current) {
+            let mut cycle = walked.split_off(position);
+            cycle.push(current);
+            cycle.reverse();
+            return Some(cycle);
+        }
+        positions.insert(current.clone(), walked.len());
+        walked.push(current.clone());
+        current = predecessors.get(&current)?.1.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::traceback;
+    use crate::graph::Edge;
+
+    // Sums the weights along a path, panicking if an edge is missing.
+    fn cost(graph: &HashMap<char, Vec<Edge<char, i64>>>, path: &[char]) -> i64 {
+        path.windows(2)
+            .map(|pair| {
+                graph[&pair[0]]
+                    .iter()
+                    .find(|edge| edge.dest == pair[1])
+                    .unwrap()
+                    .w
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_rebates() {
+        // Going the long way round through c earns a rebate that makes it
+        // cheaper than the direct edge.
+        let graph: HashMap<char, Vec<Edge<char, i64>>> = HashMap::from([
+            (
+                'a',
+                vec![Edge { dest: 'b', w: 4 }, Edge { dest: 'c', w: 5 }],
+            ),
+            ('c', vec![Edge { dest: 'b', w: -3 }]),
+            ('b', vec![Edge { dest: 'd', w: 2 }]),
+            // A zero-weight loop is not a problem.
+            ('d', vec![Edge { dest: 'e', w: 0 }]),
+            ('e', vec![Edge { dest: 'd', w: 0 }]),
+        ]);
+        let distances = bellman_ford(&graph, 'a').unwrap();
+        assert_eq!(distances[&'b'], (2, 'c'));
+        assert_eq!(distances[&'e'].0, 4);
+        assert_eq!(
+            traceback(&distances, 'a', 'e'),
+            Some(vec!['a', 'c', 'b', 'd', 'e'])
+        );
+        assert!(!distances.contains_key(&'a'));
+    }
+
+    #[test]
+    fn test_negative_cycle() {
+        let graph: HashMap<char, Vec<Edge<char, i64>>> = HashMap::from([
+            ('a', vec![Edge { dest: 'b', w: 1 }]),
+            ('b', vec![Edge { dest: 'c', w: 1 }]),
+            ('c', vec![Edge { dest: 'd', w: 1 }]),
+            (
+                'd',
+                vec![Edge { dest: 'b', w: -3 }, Edge { dest: 'e', w: 1 }],
+            ),
+        ]);
+        let cycle = match bellman_ford(&graph, 'a') {
+            Err(SearchError::NegativeCycle(cycle)) => cycle,
+            other => panic!("expected a negative cycle, got {:?}", other),
+        };
+        assert_eq!(cycle.len(), 4);
+        assert_eq!(cycle.first(), cycle.last());
+        let mut nodes = cycle[1..].to_vec();
+        nodes.sort();
+        assert_eq!(nodes, vec!['b', 'c', 'd']);
+        assert_eq!(cost(&graph, &cycle), -1);
+    }
+
+    #[test]
+    fn test_negative_cycle_through_source() {
+        let graph: HashMap<char, Vec<Edge<char, i64>>> = HashMap::from([
+            ('a', vec![Edge { dest: 'b', w: 2 }]),
+            ('b', vec![Edge { dest: 'a', w: -5 }]),
+        ]);
+        let error = bellman_ford(&graph, 'a').unwrap_err();
+        let SearchError::NegativeCycle(cycle) = error else {
+            panic!("expected a negative cycle, got {:?}", error);
+        };
+        assert!(cycle == vec!['a', 'b', 'a'] || cycle == vec!['b', 'a', 'b']);
+    }
+
+    #[test]
+    fn test_unreachable_cycle_is_ignored() {
+        let graph: HashMap<char, Vec<Edge<char, i64>>> = HashMap::from([
+            ('a', vec![Edge { dest: 'b', w: -1 }]),
+            ('x', vec![Edge { dest: 'y', w: -1 }]),
+            ('y', vec![Edge { dest: 'x', w: -1 }]),
+        ]);
+        let distances = bellman_ford(&graph, 'a').unwrap();
+        assert_eq!(distances.len(), 1);
+        assert_eq!(distances[&'b'].0, -1);
+    }
+}
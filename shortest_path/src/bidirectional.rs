@@ -0,0 +1,247 @@
+// Dijkstra from both ends at once: forwards from the source over the graph,
+// and backwards from the target over the reverse graph. Each side covers a
+// disc of about half the radius, which on a road-like graph is about half the
+// nodes between them.
+//
+// Every time either side finds a better way to a node the other side has also
+// reached, that makes a route, and we keep the best one. Once the smallest
+// distances left on the two queues add up to at least that route, nothing left
+// can beat it.
+
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::dijkstra::SearchOutcome;
+use crate::error::SearchError;
+use crate::graph::{Edge, Graph};
+use crate::weight::Weight;
+
+/// A shortest path from `source` to `target`, found by searching forwards over
+/// `graph` and backwards over `reverse` until they meet. `reverse` has to be
+/// `graph` with every edge turned round; `graph::reverse` will make one, and
+/// an undirected graph is its own reverse. Gives `Found` or `Unreachable`.
+pub fn bidirectional_dijkstra<G, R, T, W>(
+    graph: &G,
+    reverse: &R,
+    source: T,
+    target: T,
+) -> Result<SearchOutcome<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    R: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+{
+    let mut forward: Frontier<T, W> = Frontier::new(source.clone());
+    let mut backward: Frontier<T, W> = Frontier::new(target.clone());
+    // The cost of the best route so far and the node the two sides met at.
+    let mut best: Option<(W, T)> = if source == target {
+        Some((W::zero(), source))
+    } else {
+        None
+    };
+
+    // If either side runs out, it has seen everything it can reach, so any
+    // route there is has already been found.
+    while let (Some(ahead), Some(behind)) = (forward.peek(), backward.peek()) {
+        if let Some((cost, _)) = &best {
+            if ahead.checked_add(&behind).is_none_or(|sum| sum >= *cost) {
+                break;
+            }
+        }
+        if ahead <= behind {
+            forward.expand(graph, &backward, &mut best)?;
+        } else {
+            backward.expand(reverse, &forward, &mut best)?;
+        }
+    }
+
+    let (cost, meeting) = match best {
+        Some(best) => best,
+        None => return Ok(SearchOutcome::Unreachable),
+    };
+    let mut path = forward.path_to(meeting.clone());
+    path.reverse();
+    path.extend(backward.path_to(meeting).into_iter().skip(1));
+    Ok(SearchOutcome::Found { path, cost })
+}
+
+// One side of the search.
+struct Frontier<T, W> {
+    // The distance to every node this side has reached, and the node it came
+    // from. The start has no predecessor.
+    distances: HashMap<T, (W, Option<T>)>,
+    queue: BinaryHeap<Edge<T, W>>,
+    settled: HashSet<T>,
+}
+
+impl<T: Eq + Ord + Hash + Clone, W: Weight> Frontier<T, W> {
+    fn new(start: T) -> Self {
+        Frontier {
+            distances: HashMap::from([(start.clone(), (W::zero(), None))]),
+            queue: BinaryHeap::from([Edge {
+                dest: start,
+                w: W::zero(),
+            }]),
+            settled: HashSet::new(),
+        }
+    }
+
+    // The distance to the next node this side will expand. Throws away queue
+    // entries for nodes that were settled by a better one.
+    fn peek(&mut self) -> Option<W> {
+        while let Some(top) = self.queue.peek() {
+            if !self.settled.contains(&top.dest) {
+                return Some(top.w.clone());
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    // Settles the nearest node and relaxes the edges out of it. Only to be
+    // called after peek has said there is one.
+    fn expand<G: Graph<Node = T, Weight = W>>(
+        &mut self,
+        graph: &G,
+        other: &Frontier<T, W>,
+        best: &mut Option<(W, T)>,
+    ) -> Result<(), SearchError<T>> {
+        let current = self.queue.pop().unwrap();
+        self.settled.insert(current.dest.clone());
+        for neighbor in graph.neighbors(&current.dest) {
+            let alt: W =
+                current
+                    .w
+                    .checked_add(&neighbor.w)
+                    .ok_or_else(|| SearchError::Overflow {
+                        from: current.dest.clone(),
+                        to: neighbor.dest.clone(),
+                    })?;
+            let is_better: bool = match self.distances.get(&neighbor.dest) {
+                Some((distance, _pred)) => alt < *distance,
+                None => true,
+            };
+            if !is_better {
+                continue;
+            }
+            if let Some((rest, _pred)) = other.distances.get(&neighbor.dest) {
+                // A route too long to add up can't be the best one.
+                if let Some(total) = alt.checked_add(rest) {
+                    if best.as_ref().is_none_or(|(cost, _)| total < *cost) {
+                        *best = Some((total, neighbor.dest.clone()));
+                    }
+                }
+            }
+            self.distances.insert(
+                neighbor.dest.clone(),
+                (alt.clone(), Some(current.dest.clone())),
+            );
+            self.queue.push(Edge {
+                dest: neighbor.dest,
+                w: alt,
+            });
+        }
+        Ok(())
+    }
+
+    // The way back from `node` to where this side started, `node` first.
+    fn path_to(&self, node: T) -> Vec<T> {
+        let mut path = vec![];
+        let mut current = Some(node);
+        while let Some(node) = current {
+            current = self.distances[&node].1.clone();
+            path.push(node);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::find_path;
+    use crate::graph::{from_fn, reverse};
+
+    fn example() -> HashMap<char, Vec<Edge<char>>> {
+        HashMap::from([
+            (
+                'a',
+                vec![
+                    Edge { dest: 'b', w: 1 },
+                    Edge { dest: 'c', w: 9 },
+                    Edge { dest: 'e', w: 9 },
+                ],
+            ),
+            ('b', vec![Edge { dest: 'c', w: 1 }]),
+            (
+                'c',
+                vec![Edge { dest: 'd', w: 1 }, Edge { dest: 'e', w: 9 }],
+            ),
+            (
+                'd',
+                vec![Edge { dest: 'e', w: 1 }, Edge { dest: 'f', w: 9 }],
+            ),
+            ('e', vec![Edge { dest: 'f', w: 1 }]),
+        ])
+    }
+
+    #[test]
+    fn test_example_graph() {
+        let graph = example();
+        let backwards = reverse(&graph, "abcdef".chars());
+        assert_eq!(
+            bidirectional_dijkstra(&graph, &backwards, 'a', 'f'),
+            Ok(SearchOutcome::Found {
+                path: vec!['a', 'b', 'c', 'd', 'e', 'f'],
+                cost: 5
+            })
+        );
+        assert_eq!(
+            bidirectional_dijkstra(&graph, &backwards, 'f', 'a'),
+            Ok(SearchOutcome::Unreachable)
+        );
+        assert_eq!(
+            bidirectional_dijkstra(&graph, &backwards, 'c', 'c'),
+            Ok(SearchOutcome::Found {
+                path: vec!['c'],
+                cost: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_agrees_with_dijkstra() {
+        // A one-way grid: edges only go right and down, with weights that
+        // depend on where they are.
+        let weight = |(x, y): (u64, u64)| 1 + (x * 7 + y * 13) % 10;
+        let grid = from_fn(move |&(x, y): &(u64, u64)| {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| x < 12 && y < 12)
+                .map(move |dest| Edge {
+                    dest,
+                    w: weight((x, y)),
+                })
+        });
+        let nodes: Vec<(u64, u64)> = (0..12).flat_map(|x| (0..12).map(move |y| (x, y))).collect();
+        let backwards = reverse(&grid, nodes.iter().copied());
+        for &source in nodes.iter().step_by(5) {
+            for &target in nodes.iter().step_by(7) {
+                let expected = find_path(&grid, source, |&node| node == target, None).unwrap();
+                let outcome = bidirectional_dijkstra(&grid, &backwards, source, target).unwrap();
+                match (&expected, &outcome) {
+                    (
+                        SearchOutcome::Found { cost: a, .. },
+                        SearchOutcome::Found { cost: b, path },
+                    ) => {
+                        assert_eq!(a, b);
+                        assert_eq!(path.first(), Some(&source));
+                        assert_eq!(path.last(), Some(&target));
+                    }
+                    _ => assert_eq!(expected, outcome),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Which nodes can get to which. Connected components ignore which way the
+// edges point, so for a directed graph they are what are usually called the
+// weakly connected components. Strongly connected components, where every
+// node can reach every other one following the edges, come from Tarjan's
+// algorithm, done with an explicit stack so a long chain of nodes can't
+// overflow the real one.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::graph::Graph;
+use crate::union_find::UnionFind;
+
+/// Splits the graph into pieces that are connected if you ignore the
+/// direction of the edges. `nodes` has to list every node with edges out of
+/// it. The pieces come out in the order their first node turns up, listed
+/// the same way.
+pub fn connected_components<G, T, I>(graph: &G, nodes: I) -> Vec<Vec<T>>
+where
+    G: Graph<Node = T>,
+    T: Eq + Hash + Clone,
+    I: IntoIterator<Item = T>,
+{
+    let mut order: Vec<T> = vec![];
+    let mut numbers: HashMap<T, usize> = HashMap::new();
+    let mut sets = UnionFind::new(0);
+    let mut number = |node: &T, sets: &mut UnionFind, order: &mut Vec<T>| -> usize {
+        *numbers.entry(node.clone()).or_insert_with(|| {
+            order.push(node.clone());
+            sets.push()
+        })
+    };
+    for from in nodes {
+        let a = number(&from, &mut sets, &mut order);
+        for edge in graph.neighbors(&from) {
+            let b = number(&edge.dest, &mut sets, &mut order);
+            sets.union(a, b);
+        }
+    }
+
+    let mut components: Vec<Vec<T>> = vec![];
+    // Where each set's component is in the output.
+    let mut slots: HashMap<usize, usize> = HashMap::new();
+    for (number, node) in order.into_iter().enumerate() {
+        let root = sets.find(number);
+        let slot = *slots.entry(root).or_insert_with(|| {
+            components.push(vec![]);
+            components.len() - 1
+        });
+        components[slot].push(node);
+    }
+    components
+}
+
+/// Splits the graph into strongly connected components by Tarjan's
+/// algorithm. `nodes` has to list every node with edges out of it. A
+/// component comes out before any component with edges into it, which is to
+/// say in reverse topological order.
+pub fn strongly_connected_components<G, T, I>(graph: &G, nodes: I) -> Vec<Vec<T>>
+where
+    G: Graph<Node = T>,
+    T: Eq + Hash + Clone,
+    I: IntoIterator<Item = T>,
+{
+    let mut tarjan = Tarjan {
+        numbers: HashMap::new(),
+        by_number: vec![],
+        low: vec![],
+        on_stack: vec![],
+        stack: vec![],
+        path: vec![],
+        components: vec![],
+    };
+    for root in nodes {
+        if tarjan.numbers.contains_key(&root) {
+            continue;
+        }
+        tarjan.arrive(graph, root);
+        while let Some((node, next)) = tarjan.path.last_mut() {
+            let node = *node;
+            match next.next() {
+                Some(neighbor) => match tarjan.numbers.get(&neighbor) {
+                    None => tarjan.arrive(graph, neighbor),
+                    Some(&seen) if tarjan.on_stack[seen] => {
+                        tarjan.low[node] = tarjan.low[node].min(seen)
+                    }
+                    Some(_) => {}
+                },
+                None => tarjan.leave(node),
+            }
+        }
+    }
+    tarjan.components
+}
+
+// The state of Tarjan's algorithm. Nodes are numbered in the order the search
+// first reaches them.
+struct Tarjan<T> {
+    numbers: HashMap<T, usize>,
+    by_number: Vec<T>,
+    // The lowest number reachable from each node's subtree through a node that
+    // is still on the stack.
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    // Where the depth-first search is: each node on the way down, with the
+    // neighbors it has left to look at.
+    path: Vec<(usize, std::vec::IntoIter<T>)>,
+    components: Vec<Vec<T>>,
+}
+
+impl<T: Eq + Hash + Clone> Tarjan<T> {
+    fn arrive<G: Graph<Node = T>>(&mut self, graph: &G, node: T) {
+        let number = self.by_number.len();
+        let next: Vec<T> = graph.neighbors(&node).map(|edge| edge.dest).collect();
+        self.numbers.insert(node.clone(), number);
+        self.by_number.push(node);
+        self.low.push(number);
+        self.on_stack.push(true);
+        self.stack.push(number);
+        self.path.push((number, next.into_iter()));
+    }
+
+    // Called when we have looked at all of a node's neighbors. If nothing
+    // below it could get back above it, it and everything after it on the
+    // stack are a component.
+    fn leave(&mut self, node: usize) {
+        self.path.pop();
+        if let Some((parent, _)) = self.path.last() {
+            self.low[*parent] = self.low[*parent].min(self.low[node]);
+        }
+        if self.low[node] != node {
+            return;
+        }
+        let mut component = vec![];
+        loop {
+            let member = self.stack.pop().unwrap();
+            self.on_stack[member] = false;
+            component.push(self.by_number[member].clone());
+            if member == node {
+                break;
+            }
+        }
+        component.reverse();
+        self.components.push(component);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{from_fn, AdjacencyMap, Edge};
+
+    fn directed(edges: &[(char, char)]) -> AdjacencyMap<char> {
+        let mut graph: AdjacencyMap<char> = HashMap::new();
+        for &(a, b) in edges {
+            graph.entry(a).or_default().push(Edge { dest: b, w: 1 });
+        }
+        graph
+    }
+
+    fn sorted(mut components: Vec<Vec<char>>) -> Vec<Vec<char>> {
+        for component in components.iter_mut() {
+            component.sort();
+        }
+        components
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let graph = directed(&[('a', 'b'), ('c', 'b'), ('d', 'e'), ('f', 'f')]);
+        assert_eq!(
+            connected_components(&graph, "abcdefg".chars()),
+            vec![vec!['a', 'b', 'c'], vec!['d', 'e'], vec!['f'], vec!['g']]
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        // The example from the Wikipedia article on strongly connected
+        // components.
+        let graph = directed(&[
+            ('a', 'b'),
+            ('b', 'c'),
+            ('b', 'e'),
+            ('b', 'f'),
+            ('c', 'd'),
+            ('c', 'g'),
+            ('d', 'c'),
+            ('d', 'h'),
+            ('e', 'a'),
+            ('e', 'f'),
+            ('f', 'g'),
+            ('g', 'f'),
+            ('h', 'd'),
+            ('h', 'g'),
+        ]);
+        let components = strongly_connected_components(&graph, "abcdefgh".chars());
+        assert_eq!(
+            sorted(components),
+            vec![vec!['f', 'g'], vec!['c', 'd', 'h'], vec!['a', 'b', 'e']]
+        );
+    }
+
+    #[test]
+    fn test_long_chain() {
+        // 0 -> 1 -> ... -> 199999 -> 0 is one big component, and deep enough
+        // to overflow the stack if we were recursing.
+        const LEN: u32 = 200_000;
+        let ring = from_fn(|&n: &u32| {
+            [Edge {
+                dest: (n + 1) % LEN,
+                w: 1u64,
+            }]
+        });
+        let components = strongly_connected_components(&ring, 0..LEN);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), LEN as usize);
+
+        let line = from_fn(|&n: &u32| {
+            (n + 1 < LEN).then_some(Edge {
+                dest: n + 1,
+                w: 1u64,
+            })
+        });
+        let components = strongly_connected_components(&line, 0..LEN);
+        assert_eq!(components.len(), LEN as usize);
+        assert_eq!(components[0], vec![LEN - 1]);
+    }
+}
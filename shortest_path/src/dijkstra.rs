@@ -0,0 +1,691 @@
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::error::SearchError;
+use crate::graph::{Edge, Graph};
+use crate::weight::Weight;
+
+/// The distance to and predecessor of every node a search found a route to.
+pub type Predecessors<T, W> = HashMap<T, (W, T)>;
+
+/// How a search for a path from one node to a goal went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchOutcome<T, W> {
+    /// A shortest path to a goal, both ends included, and what it costs.
+    Found { path: Vec<T>, cost: W },
+    /// Every node that can be reached was looked at, and none was a goal.
+    Unreachable,
+    /// The search gave up after expanding as many nodes as it was allowed to,
+    /// without finding a goal.
+    Exhausted,
+}
+
+/// Follows the predecessors `dijkstra` recorded back from `dest` to `source`
+/// and returns the path between them, both ends included. None if the search
+/// never found a route to `dest`.
+pub fn traceback<T: Eq + Hash + Clone, W>(
+    distances: &Predecessors<T, W>,
+    source: T,
+    dest: T,
+) -> Option<Vec<T>> {
+    let mut output: Vec<T> = vec![];
+    let mut current: T = dest;
+    while current != source {
+        let (_dist, pred) = distances.get(&current)?;
+        output.push(current);
+        current = pred.clone();
+    }
+    output.push(source);
+    output.reverse();
+    Some(output)
+}
+
+/// `traceback` for a search that started from several places: follows the
+/// predecessors back from `dest` to whichever of `sources` they lead to.
+pub fn traceback_from_any<T: Eq + Hash + Clone, W>(
+    distances: &Predecessors<T, W>,
+    sources: &[T],
+    dest: T,
+) -> Option<Vec<T>> {
+    let mut output: Vec<T> = vec![];
+    let mut current: T = dest;
+    while !sources.contains(&current) {
+        let (_dist, pred) = distances.get(&current)?;
+        output.push(current);
+        current = pred.clone();
+    }
+    output.push(current);
+    output.reverse();
+    Some(output)
+}
+
+/// Finds a shortest path from `source` to the nearest node where `is_goal` is
+/// true. With `max_expanded`, it gives up with `SearchOutcome::Exhausted`
+/// after expanding that many nodes. The weights must not be negative.
+pub fn find_path<G, T, W, F>(
+    graph: &G,
+    source: T,
+    is_goal: F,
+    max_expanded: Option<usize>,
+) -> Result<SearchOutcome<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    F: Fn(&T) -> bool,
+{
+    let sources = [source];
+    let (stop, distances) = best_first(graph, &sources, is_goal, |_| W::zero(), max_expanded)?;
+    Ok(outcome(stop, &distances, &sources))
+}
+
+/// `find_path` by way of `astar`, with the same rules for `heuristic`.
+pub fn astar_path<G, T, W, F, H>(
+    graph: &G,
+    source: T,
+    is_goal: F,
+    heuristic: H,
+    max_expanded: Option<usize>,
+) -> Result<SearchOutcome<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    F: Fn(&T) -> bool,
+    H: Fn(&T) -> W,
+{
+    let sources = [source];
+    let (stop, distances) = best_first(graph, &sources, is_goal, heuristic, max_expanded)?;
+    Ok(outcome(stop, &distances, &sources))
+}
+
+/// Finds a shortest path to each of `targets` from whichever of `sources` is
+/// nearest to it, stopping as soon as every target has been reached. The
+/// outcomes come back in the same order as the targets, and are `Found` or
+/// `Unreachable`.
+pub fn paths_to_all<G, T, W, I>(
+    graph: &G,
+    sources: I,
+    targets: &[T],
+) -> Result<Vec<SearchOutcome<T, W>>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    I: IntoIterator<Item = T>,
+{
+    let sources: Vec<T> = sources.into_iter().collect();
+    let mut remaining: HashSet<T> = targets.iter().cloned().collect();
+    let (_, distances) = best_first(
+        graph,
+        &sources,
+        |node: &T| {
+            remaining.remove(node);
+            remaining.is_empty()
+        },
+        |_| W::zero(),
+        None,
+    )?;
+    Ok(targets
+        .iter()
+        .map(|target| {
+            let stop = if distances.contains_key(target) || sources.contains(target) {
+                Stop::Goal(target.clone())
+            } else {
+                Stop::Dry
+            };
+            outcome(stop, &distances, &sources)
+        })
+        .collect())
+}
+
+fn outcome<T: Eq + Hash + Clone, W: Weight>(
+    stop: Stop<T>,
+    distances: &Predecessors<T, W>,
+    sources: &[T],
+) -> SearchOutcome<T, W> {
+    match stop {
+        Stop::Goal(goal) => SearchOutcome::Found {
+            cost: match distances.get(&goal) {
+                Some((distance, _pred)) => distance.clone(),
+                None => W::zero(),
+            },
+            path: traceback_from_any(distances, sources, goal)
+                .expect("the search found a route to the goal"),
+        },
+        Stop::Dry => SearchOutcome::Unreachable,
+        Stop::OutOfBudget => SearchOutcome::Exhausted,
+    }
+}
+
+/// Searches outward from `source` until it reaches a node where `is_goal` is
+/// true. Returns that node, if there was one, along with the distance to and
+/// predecessor of every node it found a route to, or an error if some distance
+/// got too big for the weight type. The weights must not be negative; use
+/// `bellman_ford` if some are.
+#[allow(clippy::type_complexity)]
+pub fn dijkstra<G, T, W, F>(
+    graph: &G,
+    source: T,
+    is_goal: F,
+) -> Result<(Option<T>, Predecessors<T, W>), SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    F: Fn(&T) -> bool,
+{
+    let (stop, distances) = best_first(graph, &[source], is_goal, |_| W::zero(), None)?;
+    Ok((stop.goal(), distances))
+}
+
+/// `dijkstra` starting from all of `sources` at once, so the goal it finds is
+/// the one nearest to any of them. Use `traceback_from_any` to get the path.
+#[allow(clippy::type_complexity)]
+pub fn multi_source_dijkstra<G, T, W, I, F>(
+    graph: &G,
+    sources: I,
+    is_goal: F,
+) -> Result<(Option<T>, Predecessors<T, W>), SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> bool,
+{
+    let sources: Vec<T> = sources.into_iter().collect();
+    let (stop, distances) = best_first(graph, &sources, is_goal, |_| W::zero(), None)?;
+    Ok((stop.goal(), distances))
+}
+
+/// Like `dijkstra`, but goes towards the goal first. `heuristic` guesses the
+/// distance from a node to the nearest goal, and has to be admissible: it can
+/// guess too low but never too high, or the path found may not be the
+/// shortest. The better the guess, the fewer nodes the search looks at.
+#[allow(clippy::type_complexity)]
+pub fn astar<G, T, W, F, H>(
+    graph: &G,
+    source: T,
+    is_goal: F,
+    heuristic: H,
+) -> Result<(Option<T>, Predecessors<T, W>), SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    F: Fn(&T) -> bool,
+    H: Fn(&T) -> W,
+{
+    let (stop, distances) = best_first(graph, &[source], is_goal, heuristic, None)?;
+    Ok((stop.goal(), distances))
+}
+
+// Why best_first stopped.
+enum Stop<T> {
+    Goal(T),
+    // The queue ran out.
+    Dry,
+    // It expanded as many nodes as it was allowed to.
+    OutOfBudget,
+}
+
+impl<T> Stop<T> {
+    fn goal(self) -> Option<T> {
+        match self {
+            Stop::Goal(goal) => Some(goal),
+            _ => None,
+        }
+    }
+}
+
+// The search behind all of them. Queue entries hold the distance so far plus
+// the heuristic's guess for the rest, and with a guess of zero this is plain
+// Dijkstra. There is no visited set: a node comes off the queue again only if
+// we found a shorter way to it since, which can happen with a heuristic that is
+// admissible but not consistent.
+#[allow(clippy::type_complexity)]
+fn best_first<G, T, W, F, H>(
+    graph: &G,
+    sources: &[T],
+    mut is_goal: F,
+    heuristic: H,
+    max_expanded: Option<usize>,
+) -> Result<(Stop<T>, Predecessors<T, W>), SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    F: FnMut(&T) -> bool,
+    H: Fn(&T) -> W,
+{
+    let mut distances: Predecessors<T, W> = Default::default();
+    let mut queue: BinaryHeap<Edge<T, W>> = BinaryHeap::new();
+    let mut expanded: usize = 0;
+
+    let starts: HashSet<&T> = sources.iter().collect();
+    for source in starts.iter() {
+        queue.push(Edge {
+            w: heuristic(source),
+            dest: (*source).clone(),
+        });
+    }
+    while let Some(current) = queue.pop() {
+        // Sources never get an entry in distances, so that traceback knows
+        // where to stop.
+        let so_far: W = match distances.get(&current.dest) {
+            Some((distance, _pred)) => distance.clone(),
+            None => W::zero(),
+        };
+        // We may have queued this node again after finding a better route,
+        // in which case this entry is out of date.
+        if estimate(&so_far, &current.dest, &current.dest, &heuristic)? < current.w {
+            continue;
+        }
+        if is_goal(&current.dest) {
+            return Ok((Stop::Goal(current.dest), distances));
+        }
+        if max_expanded == Some(expanded) {
+            return Ok((Stop::OutOfBudget, distances));
+        }
+        expanded += 1;
+        for neighbor in graph.neighbors(&current.dest) {
+            if starts.contains(&neighbor.dest) {
+                continue;
+            }
+            let alt: W = so_far
+                .checked_add(&neighbor.w)
+                .ok_or_else(|| SearchError::Overflow {
+                    from: current.dest.clone(),
+                    to: neighbor.dest.clone(),
+                })?;
+            let is_better: bool = match distances.get(&neighbor.dest) {
+                Some((distance, _pred)) => alt < *distance,
+                None => true,
+            };
+            if is_better {
+                let w = estimate(&alt, &current.dest, &neighbor.dest, &heuristic)?;
+                distances.insert(neighbor.dest.clone(), (alt, current.dest.clone()));
+                queue.push(Edge {
+                    dest: neighbor.dest,
+                    w,
+                });
+            }
+        }
+    }
+    Ok((Stop::Dry, distances))
+}
+
+// The distance to `to` plus the heuristic's guess for the rest of the way.
+fn estimate<T: Clone, W: Weight, H: Fn(&T) -> W>(
+    so_far: &W,
+    from: &T,
+    to: &T,
+    heuristic: &H,
+) -> Result<W, SearchError<T>> {
+    so_far
+        .checked_add(&heuristic(to))
+        .ok_or_else(|| SearchError::Overflow {
+            from: from.clone(),
+            to: to.clone(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{from_fn, AdjacencyMatrix};
+    use crate::heuristic::{chebyshev, manhattan};
+    use crate::weight::OrderedFloat;
+
+    fn example() -> HashMap<char, Vec<Edge<char>>> {
+        HashMap::from([
+            (
+                'a',
+                vec![
+                    Edge { dest: 'b', w: 1 },
+                    Edge { dest: 'c', w: 9 },
+                    Edge { dest: 'e', w: 9 },
+                ],
+            ),
+            ('b', vec![Edge { dest: 'c', w: 1 }]),
+            (
+                'c',
+                vec![Edge { dest: 'd', w: 1 }, Edge { dest: 'e', w: 9 }],
+            ),
+            (
+                'd',
+                vec![Edge { dest: 'e', w: 1 }, Edge { dest: 'f', w: 9 }],
+            ),
+            ('e', vec![Edge { dest: 'f', w: 1 }]),
+        ])
+    }
+
+    #[test]
+    fn test_example_graph() {
+        let (found, distances) = dijkstra(&example(), 'a', |x| *x == 'f').unwrap();
+        assert_eq!(found, Some('f'));
+        assert_eq!(distances[&'f'].0, 5);
+        assert_eq!(
+            traceback(&distances, 'a', 'f'),
+            Some(vec!['a', 'b', 'c', 'd', 'e', 'f'])
+        );
+    }
+
+    #[test]
+    fn test_sink_nodes_do_not_panic() {
+        // f has no list of its own in the map, and the goal is unreachable,
+        // so the search has to run dry on it.
+        let (found, distances) = dijkstra(&example(), 'a', |x| *x == 'z').unwrap();
+        assert_eq!(found, None);
+        assert_eq!(distances.len(), 5);
+        assert_eq!(traceback(&distances, 'a', 'z'), None);
+        assert_eq!(traceback(&distances, 'a', 'a'), Some(vec!['a']));
+    }
+
+    #[test]
+    fn test_find_path() {
+        assert_eq!(
+            find_path(&example(), 'a', |x| *x == 'f', None),
+            Ok(SearchOutcome::Found {
+                path: vec!['a', 'b', 'c', 'd', 'e', 'f'],
+                cost: 5
+            })
+        );
+        assert_eq!(
+            find_path(&example(), 'a', |x| *x == 'a', None),
+            Ok(SearchOutcome::Found {
+                path: vec!['a'],
+                cost: 0
+            })
+        );
+        assert_eq!(
+            find_path(&example(), 'c', |x| *x == 'a', None),
+            Ok(SearchOutcome::Unreachable)
+        );
+        // a to e all have to be expanded before f comes off the queue.
+        assert_eq!(
+            find_path(&example(), 'a', |x| *x == 'f', Some(4)),
+            Ok(SearchOutcome::Exhausted)
+        );
+        assert!(matches!(
+            find_path(&example(), 'a', |x| *x == 'f', Some(5)),
+            Ok(SearchOutcome::Found { cost: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_astar_path_on_an_endless_graph() {
+        // The number line goes on forever, so without a goal the only way to
+        // stop is to run out of budget.
+        let line = from_fn(|&n: &i64| {
+            [
+                Edge {
+                    dest: n - 1,
+                    w: 1u64,
+                },
+                Edge { dest: n + 1, w: 1 },
+            ]
+        });
+        let towards_ten = |n: &i64| n.abs_diff(10);
+        assert_eq!(
+            astar_path(&line, 0, |&n| n == 10, towards_ten, Some(100)),
+            Ok(SearchOutcome::Found {
+                path: (0..=10).collect(),
+                cost: 10
+            })
+        );
+        assert_eq!(
+            astar_path(&line, 0, |_| false, towards_ten, Some(100)),
+            Ok(SearchOutcome::Exhausted)
+        );
+    }
+
+    #[test]
+    fn test_matrix() {
+        let mut graph = AdjacencyMatrix::new(4);
+        graph.set_edge(0, 1, 5);
+        graph.set_edge(0, 2, 1);
+        graph.set_edge(2, 1, 1);
+        graph.set_edge(1, 3, 1);
+        let (found, distances) = dijkstra(&graph, 0, |n| *n == 3).unwrap();
+        assert_eq!(found, Some(3));
+        assert_eq!(traceback(&distances, 0, 3), Some(vec![0, 2, 1, 3]));
+    }
+
+    #[test]
+    fn test_implicit_grid() {
+        // A 10x10 grid with a wall down x = 5 that has a gap at y = 9.
+        let grid = from_fn(|&(x, y): &(i64, i64)| {
+            [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .map(move |(dx, dy)| (x + dx, y + dy))
+                .filter(|&(x, y)| (0..10).contains(&x) && (0..10).contains(&y))
+                .filter(|&(x, y)| x != 5 || y == 9)
+                .map(|dest| Edge { dest, w: 1 })
+        });
+        let (found, distances) = dijkstra(&grid, (0, 0), |&node| node == (9, 0)).unwrap();
+        assert_eq!(found, Some((9, 0)));
+        assert_eq!(distances[&(9, 0)].0, 9 + 9 + 9);
+    }
+
+    #[test]
+    fn test_long_paths_do_not_wrap() {
+        // 300 steps of 1 each. With u8 distances this used to overflow.
+        let line = from_fn(|&n: &u32| {
+            (n < 300).then_some(Edge {
+                dest: n + 1,
+                w: 1u32,
+            })
+        });
+        let (found, distances) = dijkstra(&line, 0, |&n| n == 300).unwrap();
+        assert_eq!(found, Some(300));
+        assert_eq!(distances[&300].0, 300);
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let graph: HashMap<char, Vec<Edge<char, u8>>> = HashMap::from([
+            ('a', vec![Edge { dest: 'b', w: 200 }]),
+            ('b', vec![Edge { dest: 'c', w: 100 }]),
+        ]);
+        let error = dijkstra(&graph, 'a', |x| *x == 'c').unwrap_err();
+        assert_eq!(error, SearchError::Overflow { from: 'b', to: 'c' });
+        assert_eq!(
+            error.to_string(),
+            "the distance to 'c' by way of 'b' overflows the weight type"
+        );
+    }
+
+    #[test]
+    fn test_float_weights() {
+        let mut graph = AdjacencyMatrix::new(3);
+        graph.set_edge(0, 2, OrderedFloat(1.0));
+        graph.set_edge(0, 1, OrderedFloat(0.25));
+        graph.set_edge(1, 2, OrderedFloat(0.5));
+        let (_, distances) = dijkstra(&graph, 0, |_| false).unwrap();
+        assert_eq!(distances[&2], (OrderedFloat(0.75), 1));
+    }
+
+    // A cost that cares about the number of transfers first and the time taken
+    // second, like a journey planner would.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Journey {
+        transfers: u8,
+        minutes: u32,
+    }
+
+    impl Weight for Journey {
+        fn zero() -> Self {
+            Journey {
+                transfers: 0,
+                minutes: 0,
+            }
+        }
+
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            Some(Journey {
+                transfers: self.transfers.checked_add(other.transfers)?,
+                minutes: self.minutes.checked_add(other.minutes)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_custom_weights() {
+        let ride = |transfers, minutes| Journey { transfers, minutes };
+        let graph = HashMap::from([
+            (
+                "home",
+                vec![
+                    Edge {
+                        dest: "work",
+                        w: ride(0, 50),
+                    },
+                    Edge {
+                        dest: "hub",
+                        w: ride(0, 10),
+                    },
+                ],
+            ),
+            (
+                "hub",
+                vec![Edge {
+                    dest: "work",
+                    w: ride(1, 10),
+                }],
+            ),
+        ]);
+        let (_, distances) = dijkstra(&graph, "home", |_| false).unwrap();
+        assert_eq!(distances[&"work"], (ride(0, 50), "home"));
+    }
+
+    // A 20x20 grid with a wall down x = 10 that has a gap at y = 19. Counts
+    // the nodes the search expands.
+    fn walled_grid(
+        expanded: &std::cell::Cell<usize>,
+    ) -> impl Graph<Node = (i64, i64), Weight = u64> + '_ {
+        from_fn(move |&(x, y): &(i64, i64)| {
+            expanded.set(expanded.get() + 1);
+            [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .map(move |(dx, dy)| (x + dx, y + dy))
+                .filter(|&(x, y)| (0..20).contains(&x) && (0..20).contains(&y))
+                .filter(|&(x, y)| x != 10 || y == 19)
+                .map(|dest| Edge { dest, w: 1 })
+        })
+    }
+
+    #[test]
+    fn test_astar_matches_dijkstra() {
+        let goal = (19, 0);
+        let dijkstra_expanded = std::cell::Cell::new(0);
+        let (_, plain) =
+            dijkstra(&walled_grid(&dijkstra_expanded), (0, 0), |&n| n == goal).unwrap();
+        let astar_expanded = std::cell::Cell::new(0);
+        let (found, guided) = astar(
+            &walled_grid(&astar_expanded),
+            (0, 0),
+            |&n| n == goal,
+            |n| manhattan(n, &goal),
+        )
+        .unwrap();
+        assert_eq!(found, Some(goal));
+        assert_eq!(guided[&goal].0, plain[&goal].0);
+        assert_eq!(guided[&goal].0, 19 + 19 + 19);
+        let path = traceback(&guided, (0, 0), goal).unwrap();
+        assert_eq!(path.len(), 19 + 19 + 19 + 1);
+        assert!(astar_expanded.get() < dijkstra_expanded.get());
+    }
+
+    #[test]
+    fn test_astar_diagonal_moves() {
+        // Kings moves on an open board, where Chebyshev distance is exact.
+        let board = from_fn(|&(x, y): &(i64, i64)| {
+            (-1..=1)
+                .flat_map(move |dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+                .filter(move |&dest| dest != (x, y))
+                .map(|dest| Edge { dest, w: 1u64 })
+        });
+        let goal = (3, 7);
+        let (found, distances) =
+            astar(&board, (0, 0), |&n| n == goal, |n| chebyshev(n, &goal)).unwrap();
+        assert_eq!(found, Some(goal));
+        assert_eq!(distances[&goal].0, 7);
+    }
+
+    #[test]
+    fn test_astar_inconsistent_heuristic() {
+        // The heuristic is admissible but not consistent: it makes b look
+        // much better than c, so b gets expanded before we know the short way
+        // to it goes through c. The search has to go back and fix that.
+        let graph: HashMap<char, Vec<Edge<char>>> = HashMap::from([
+            (
+                's',
+                vec![Edge { dest: 'b', w: 4 }, Edge { dest: 'c', w: 1 }],
+            ),
+            ('c', vec![Edge { dest: 'b', w: 1 }]),
+            ('b', vec![Edge { dest: 'g', w: 5 }]),
+        ]);
+        let guess = |n: &char| match n {
+            'c' => 6,
+            _ => 0,
+        };
+        let (found, distances) = astar(&graph, 's', |&n| n == 'g', guess).unwrap();
+        assert_eq!(found, Some('g'));
+        assert_eq!(distances[&'g'].0, 7);
+        assert_eq!(
+            traceback(&distances, 's', 'g'),
+            Some(vec!['s', 'c', 'b', 'g'])
+        );
+    }
+
+    #[test]
+    fn test_multi_source() {
+        // From b or d, the nearest of c and f is c, one step from b.
+        let (found, distances) =
+            multi_source_dijkstra(&example(), ['d', 'b'], |x| *x == 'c' || *x == 'f').unwrap();
+        assert_eq!(found, Some('c'));
+        assert_eq!(
+            traceback_from_any(&distances, &['d', 'b'], 'c'),
+            Some(vec!['b', 'c'])
+        );
+        assert_eq!(traceback_from_any(&distances, &['d', 'b'], 'a'), None);
+    }
+
+    #[test]
+    fn test_paths_to_all() {
+        let visited = std::cell::RefCell::new(vec![]);
+        let graph = example();
+        let watched = from_fn(|node: &char| {
+            visited.borrow_mut().push(*node);
+            graph.neighbors(node).collect::<Vec<_>>()
+        });
+        let outcomes = paths_to_all(&watched, ['a', 'e'], &['f', 'b', 'a', 'z']).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                SearchOutcome::Found {
+                    path: vec!['e', 'f'],
+                    cost: 1
+                },
+                SearchOutcome::Found {
+                    path: vec!['a', 'b'],
+                    cost: 1
+                },
+                SearchOutcome::Found {
+                    path: vec!['a'],
+                    cost: 0
+                },
+                SearchOutcome::Unreachable,
+            ]
+        );
+
+        // Without the unreachable z it can stop early, before d is expanded.
+        visited.borrow_mut().clear();
+        paths_to_all(&watched, ['a', 'e'], &['f', 'b']).unwrap();
+        assert!(!visited.borrow().contains(&'d'));
+    }
+}
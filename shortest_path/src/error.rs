@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fmt::{self, Debug};
+
+/// Why a search could not give an answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchError<T> {
+    /// The distance to `to` by way of `from` is too big for the weight type.
+    Overflow { from: T, to: T },
+    /// There is a cycle whose weights add up to less than zero, so going
+    /// round it again always makes a route shorter. It is written out like a
+    /// path from `traceback`, with the first node repeated at the end.
+    NegativeCycle(Vec<T>),
+}
+
+impl<T: Debug> fmt::Display for SearchError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Overflow { from, to } => write!(
+                f,
+                "the distance to {:?} by way of {:?} overflows the weight type",
+                to, from
+            ),
+            SearchError::NegativeCycle(cycle) => {
+                write!(
+                    f,
+                    "negative cycle {:?} means there is no shortest path",
+                    cycle
+                )
+            }
+        }
+    }
+}
+
+impl<T: Debug> Error for SearchError<T> {}
+
+/// Why a graph file could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
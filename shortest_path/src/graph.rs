@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::weight::Weight;
+
+/// A weighted edge to `dest`. The searches also use it for queue entries, with
+/// `w` holding the distance so far. The weight can be any `Weight`; it is
+/// `u64` unless you say otherwise.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edge<T, W = u64> {
+    pub dest: T,
+    pub w: W,
+}
+
+impl<T: Eq + Ord, W: Ord> PartialOrd for Edge<T, W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Eq + Ord, W: Ord> Ord for Edge<T, W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Notice that the we flip the ordering on costs.
+        // In case of a tie we compare positions - this step is necessary
+        // to make implementations of `PartialEq` and `Ord` consistent.
+        other
+            .w
+            .cmp(&self.w)
+            .then_with(|| self.dest.cmp(&other.dest))
+    }
+}
+
+/// Anything the searches can walk over: all they need is the edges leaving a
+/// node. Nodes with no outgoing edges simply have no neighbors.
+pub trait Graph {
+    type Node;
+    type Weight: Weight;
+
+    fn neighbors(&self, node: &Self::Node) -> impl Iterator<Item = Edge<Self::Node, Self::Weight>>;
+}
+
+impl<G: Graph> Graph for &G {
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn neighbors(&self, node: &Self::Node) -> impl Iterator<Item = Edge<Self::Node, Self::Weight>> {
+        (**self).neighbors(node)
+    }
+}
+
+/// The edges leaving each node, which is what the original dijkstra took. A
+/// node that is missing from the map is treated the same as one with an empty
+/// list.
+pub type AdjacencyMap<T, W = u64> = HashMap<T, Vec<Edge<T, W>>>;
+
+impl<T: Eq + Hash + Clone, W: Weight> Graph for AdjacencyMap<T, W> {
+    type Node = T;
+    type Weight = W;
+
+    fn neighbors(&self, node: &T) -> impl Iterator<Item = Edge<T, W>> {
+        self.get(node).into_iter().flatten().cloned()
+    }
+}
+
+/// A dense graph over the nodes `0..len()`, with at most one edge in each
+/// direction between any two of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdjacencyMatrix<W = u64> {
+    len: usize,
+    // Row-major: the edge from i to j is at i * len + j.
+    weights: Vec<Option<W>>,
+}
+
+impl<W: Clone> AdjacencyMatrix<W> {
+    /// `len` nodes and no edges.
+    pub fn new(len: usize) -> Self {
+        AdjacencyMatrix {
+            len,
+            weights: vec![None; len * len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds an edge, or replaces the weight of the one already there.
+    pub fn set_edge(&mut self, from: usize, to: usize, w: W) {
+        let index = self.index(from, to);
+        self.weights[index] = Some(w);
+    }
+
+    pub fn remove_edge(&mut self, from: usize, to: usize) {
+        let index = self.index(from, to);
+        self.weights[index] = None;
+    }
+
+    pub fn weight(&self, from: usize, to: usize) -> Option<&W> {
+        self.weights[self.index(from, to)].as_ref()
+    }
+
+    fn index(&self, from: usize, to: usize) -> usize {
+        assert!(
+            from < self.len && to < self.len,
+            "edge {} -> {} is outside a {} node matrix",
+            from,
+            to,
+            self.len
+        );
+        from * self.len + to
+    }
+}
+
+impl<W: Weight> Graph for AdjacencyMatrix<W> {
+    type Node = usize;
+    type Weight = W;
+
+    fn neighbors(&self, node: &usize) -> impl Iterator<Item = Edge<usize, W>> {
+        // A node past the end has no edges, the same as a missing key in an
+        // adjacency map. Saturating keeps a huge node from wrapping around
+        // into a real row.
+        let start = node.saturating_mul(self.len);
+        let row = self.weights.get(start..start.saturating_add(self.len));
+        row.into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(dest, w)| w.clone().map(|w| Edge { dest, w }))
+    }
+}
+
+/// A graph whose edges are worked out on demand by a closure, for things like
+/// grids where writing every edge down first would be a waste. Make one with
+/// `from_fn`.
+pub struct FromFn<T, F> {
+    neighbors: F,
+    marker: PhantomData<fn(&T)>,
+}
+
+/// Wraps a closure that lists the edges leaving a node.
+pub fn from_fn<T, W, F, I>(neighbors: F) -> FromFn<T, F>
+where
+    W: Weight,
+    F: Fn(&T) -> I,
+    I: IntoIterator<Item = Edge<T, W>>,
+{
+    FromFn {
+        neighbors,
+        marker: PhantomData,
+    }
+}
+
+impl<T, W, F, I> Graph for FromFn<T, F>
+where
+    W: Weight,
+    F: Fn(&T) -> I,
+    I: IntoIterator<Item = Edge<T, W>>,
+{
+    type Node = T;
+    type Weight = W;
+
+    fn neighbors(&self, node: &T) -> impl Iterator<Item = Edge<T, W>> {
+        (self.neighbors)(node).into_iter()
+    }
+}
+
+/// `graph` with every edge turned round, as an adjacency map. `nodes` has to
+/// list every node with edges out of it.
+pub fn reverse<G, I>(graph: &G, nodes: I) -> AdjacencyMap<G::Node, G::Weight>
+where
+    G: Graph,
+    G::Node: Eq + Hash + Clone,
+    I: IntoIterator<Item = G::Node>,
+{
+    let mut reversed: AdjacencyMap<G::Node, G::Weight> = HashMap::new();
+    for node in nodes {
+        for edge in graph.neighbors(&node) {
+            reversed.entry(edge.dest).or_default().push(Edge {
+                dest: node.clone(),
+                w: edge.w,
+            });
+        }
+    }
+    reversed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dests<G: Graph>(graph: &G, node: G::Node) -> Vec<(G::Node, G::Weight)> {
+        graph
+            .neighbors(&node)
+            .map(|edge| (edge.dest, edge.w))
+            .collect()
+    }
+
+    #[test]
+    fn test_adjacency_map() {
+        let graph: HashMap<char, Vec<Edge<char>>> =
+            HashMap::from([('a', vec![Edge { dest: 'b', w: 3 }])]);
+        assert_eq!(dests(&graph, 'a'), vec![('b', 3)]);
+        assert_eq!(dests(&graph, 'b'), vec![]);
+    }
+
+    #[test]
+    fn test_adjacency_matrix() {
+        let mut graph = AdjacencyMatrix::new(3);
+        graph.set_edge(0, 2, 7);
+        graph.set_edge(0, 1, 4);
+        graph.set_edge(2, 0, 1);
+        graph.set_edge(0, 1, 5);
+        assert_eq!(dests(&graph, 0), vec![(1, 5), (2, 7)]);
+        graph.remove_edge(0, 2);
+        assert_eq!(graph.weight(0, 2), None);
+        assert_eq!(graph.weight(0, 1), Some(&5));
+        assert_eq!(dests(&graph, 2), vec![(0, 1)]);
+        assert_eq!(dests(&graph, 1), vec![]);
+        assert_eq!(dests(&graph, 3), vec![]);
+        assert_eq!(dests(&graph, usize::MAX), vec![]);
+    }
+
+    #[test]
+    fn test_from_fn() {
+        // The number line, where a step either way costs 1 and jumping to
+        // double costs 2.
+        let graph = from_fn(|n: &i64| {
+            [
+                Edge { dest: n - 1, w: 1 },
+                Edge { dest: n + 1, w: 1 },
+                Edge { dest: n * 2, w: 2 },
+            ]
+        });
+        assert_eq!(dests(&graph, 5), vec![(4, 1), (6, 1), (10, 2)]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut graph = AdjacencyMatrix::new(3);
+        graph.set_edge(0, 1, 4);
+        graph.set_edge(0, 2, 7);
+        graph.set_edge(2, 1, 1);
+        let backwards = reverse(&graph, 0..3);
+        let mut into_one = dests(&backwards, 1);
+        into_one.sort();
+        assert_eq!(into_one, vec![(0, 4), (2, 1)]);
+        assert_eq!(dests(&backwards, 2), vec![(0, 7)]);
+        assert_eq!(dests(&backwards, 0), vec![]);
+    }
+}
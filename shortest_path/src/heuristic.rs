@@ -0,0 +1,27 @@
+//! Heuristics for `astar` on 2D grids, as distances between two points. Use
+//! them with the goal filled in, like `|node| manhattan(node, &goal)`.
+
+/// The number of steps between two points when you can only move up, down,
+/// left or right. Admissible when every step costs at least 1.
+pub fn manhattan(a: &(i64, i64), b: &(i64, i64)) -> u64 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// The number of steps between two points when you can also move diagonally,
+/// like a king in chess. Admissible when every step costs at least 1.
+pub fn chebyshev(a: &(i64, i64), b: &(i64, i64)) -> u64 {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distances() {
+        assert_eq!(manhattan(&(0, 0), &(3, -4)), 7);
+        assert_eq!(chebyshev(&(0, 0), &(3, -4)), 4);
+        assert_eq!(manhattan(&(2, 2), &(2, 2)), 0);
+        assert_eq!(chebyshev(&(i64::MIN, 0), &(i64::MAX, 0)), u64::MAX);
+    }
+}
@@ -0,0 +1,35 @@
+//! Shortest paths over anything that implements `Graph`: adjacency maps,
+//! adjacency matrices, or closures that work out the edges as they go. Edge
+//! weights can be any `Weight`: the integer types, `OrderedFloat`, or a cost
+//! type of your own. Graphs can be read from DIMACS, CSV and DOT files with
+//! the `parse` module. Next to the shortest paths there are minimum spanning
+//! trees and connected components.
+
+mod all_pairs;
+mod bellman_ford;
+mod bidirectional;
+mod components;
+mod dijkstra;
+mod error;
+pub mod graph;
+pub mod heuristic;
+pub mod parse;
+mod spanning;
+mod union_find;
+mod weight;
+mod yen;
+
+pub use all_pairs::{floyd_warshall, johnson, DistanceMatrix};
+pub use bellman_ford::bellman_ford;
+pub use bidirectional::bidirectional_dijkstra;
+pub use components::{connected_components, strongly_connected_components};
+pub use dijkstra::{
+    astar, astar_path, dijkstra, find_path, multi_source_dijkstra, paths_to_all, traceback,
+    traceback_from_any, Predecessors, SearchOutcome,
+};
+pub use error::{ParseError, SearchError};
+pub use graph::{from_fn, reverse, AdjacencyMap, AdjacencyMatrix, Edge, Graph};
+pub use spanning::{kruskal, prim, SpanningForest};
+pub use union_find::UnionFind;
+pub use weight::{OrderedFloat, Subtract, Weight};
+pub use yen::k_shortest_paths;
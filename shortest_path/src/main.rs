@@ -0,0 +1,161 @@
+// Reads a graph from a file and prints a shortest path through it:
+//
+//     shortest_path --format dimacs graph.gr --from 1 --to 6
+//
+// The format can be left out if the file name ends in .gr, .csv, .dot or .gv.
+// Weights are read as u64.
+
+use std::env;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use shortest_path::parse::{parse, Format};
+use shortest_path::{find_path, AdjacencyMap, SearchOutcome};
+
+const USAGE: &str = "usage: shortest_path [--format dimacs|csv|dot] FILE --from NODE --to NODE";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    format: Option<Format>,
+    file: PathBuf,
+    from: String,
+    to: String,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut format = None;
+    let mut file = None;
+    let mut from = None;
+    let mut to = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--format" => format = Some(value("--format")?.parse()?),
+            "--from" => from = Some(value("--from")?),
+            "--to" => to = Some(value("--to")?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ if file.is_some() => return Err(format!("unexpected argument {}", arg)),
+            _ => file = Some(PathBuf::from(arg)),
+        }
+    }
+    Ok(Options {
+        format,
+        file: file.ok_or("no graph file given")?,
+        from: from.ok_or("--from is missing")?,
+        to: to.ok_or("--to is missing")?,
+    })
+}
+
+fn load(format: Option<Format>, file: &Path) -> Result<AdjacencyMap<String, u64>, String> {
+    let format = format.or_else(|| Format::from_path(file)).ok_or(format!(
+        "can't tell the format of {}; use --format",
+        file.display()
+    ))?;
+    let input =
+        read_to_string(file).map_err(|e| format!("can't read {}: {}", file.display(), e))?;
+    parse(format, &input).map_err(|e| format!("{}: {}", file.display(), e))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let graph = load(options.format, &options.file)?;
+    print!("{}", report(&graph, &options)?);
+    Ok(())
+}
+
+// Everything run prints, so the tests can look at it without a file.
+fn report(graph: &AdjacencyMap<String, u64>, options: &Options) -> Result<String, String> {
+    for node in [&options.from, &options.to] {
+        if !graph.contains_key(node) {
+            return Err(format!("there is no node {:?} in the graph", node));
+        }
+    }
+    let to = options.to.clone();
+    match find_path(graph, options.from.clone(), |node| *node == to, None) {
+        Ok(SearchOutcome::Found { path, cost }) => {
+            Ok(format!("{}\ncost {}\n", path.join(" -> "), cost))
+        }
+        Ok(_) => Err(format!("no path from {} to {}", options.from, options.to)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+    if let Err(e) = run(options) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            args("--format dimacs graph.gr --from A --to F"),
+            Ok(Options {
+                format: Some(Format::Dimacs),
+                file: PathBuf::from("graph.gr"),
+                from: "A".to_string(),
+                to: "F".to_string(),
+            })
+        );
+        assert_eq!(args("--to b g.csv --from a").unwrap().format, None);
+        assert_eq!(args("g.csv --from a"), Err("--to is missing".to_string()));
+        assert_eq!(
+            args("g.csv --from a --to"),
+            Err("--to needs a value".to_string())
+        );
+        assert_eq!(
+            args("--verbose"),
+            Err("unknown option --verbose".to_string())
+        );
+        assert!(args("--format xml g --from a --to b").is_err());
+    }
+
+    fn report_on(format: Format, input: &str, from: &str, to: &str) -> Result<String, String> {
+        let graph = parse(format, input).unwrap();
+        let options = args(&format!("graph --from {} --to {}", from, to)).unwrap();
+        report(&graph, &options)
+    }
+
+    #[test]
+    fn test_report() {
+        let dimacs = "p sp 4 3\na 1 2 7\na 2 3 1\na 1 3 9\n";
+        assert_eq!(
+            report_on(Format::Dimacs, dimacs, "1", "3"),
+            Ok("1 -> 2 -> 3\ncost 8\n".to_string())
+        );
+        assert_eq!(
+            report_on(Format::Dimacs, dimacs, "1", "4"),
+            Err("no path from 1 to 4".to_string())
+        );
+
+        let csv = "from,to,weight\na,b,3\nb,c,4\nd,a,1\n";
+        assert_eq!(
+            report_on(Format::Csv, csv, "d", "c"),
+            Ok("d -> a -> b -> c\ncost 8\n".to_string())
+        );
+        assert_eq!(
+            report_on(Format::Csv, csv, "c", "a"),
+            Err("no path from c to a".to_string())
+        );
+        assert_eq!(
+            report_on(Format::Csv, csv, "a", "z"),
+            Err("there is no node \"z\" in the graph".to_string())
+        );
+    }
+}
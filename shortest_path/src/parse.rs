@@ -0,0 +1,561 @@
+// Reading graphs from files. All three formats come out as an adjacency map
+// keyed by the node names in the file, with every node as a key, including the
+// ones with no edges out. Weights are parsed with FromStr, so the caller picks
+// the weight type.
+//
+// DIMACS is the format of the 9th DIMACS shortest path challenge:
+//
+//     c a comment
+//     p sp <nodes> <arcs>
+//     a <from> <to> <weight>
+//
+// CSV is one `from,to,weight` edge per line, with an optional header line and
+// no quoting. DOT is the part of Graphviz's language that says what the edges
+// are: a `graph` or `digraph` with node, edge and attribute statements. An
+// edge's weight comes from its `weight` attribute, or failing that its
+// `label`, or failing that an `edge [weight=...]` default, or else it is 1.
+// Subgraphs and ports are not supported.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::ParseError;
+use crate::graph::{AdjacencyMap, Edge};
+
+/// The file formats `parse` can read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Dimacs,
+    Csv,
+    Dot,
+}
+
+impl Format {
+    /// Guesses the format from a file name: .gr, .csv, or .dot or .gv.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "gr" => Some(Format::Dimacs),
+            "csv" => Some(Format::Csv),
+            "dot" | "gv" => Some(Format::Dot),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "dimacs" => Ok(Format::Dimacs),
+            "csv" => Ok(Format::Csv),
+            "dot" => Ok(Format::Dot),
+            _ => Err(format!(
+                "unknown format {:?}; expected dimacs, csv or dot",
+                name
+            )),
+        }
+    }
+}
+
+/// Reads a graph in the given format.
+pub fn parse<W: FromStr>(
+    format: Format,
+    input: &str,
+) -> Result<AdjacencyMap<String, W>, ParseError> {
+    match format {
+        Format::Dimacs => parse_dimacs(input),
+        Format::Csv => parse_csv(input),
+        Format::Dot => parse_dot(input),
+    }
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        line,
+        message: message.into(),
+    })
+}
+
+fn weight<W: FromStr>(line: usize, text: &str) -> Result<W, ParseError> {
+    match text.parse() {
+        Ok(w) => Ok(w),
+        Err(_) => error(line, format!("{:?} is not a valid weight", text)),
+    }
+}
+
+fn add_edge<W>(graph: &mut AdjacencyMap<String, W>, from: &str, to: &str, w: W) {
+    graph.entry(to.to_string()).or_default();
+    graph.entry(from.to_string()).or_default().push(Edge {
+        dest: to.to_string(),
+        w,
+    });
+}
+
+/// Reads a DIMACS `.gr` file. Nodes are named by their numbers.
+pub fn parse_dimacs<W: FromStr>(input: &str) -> Result<AdjacencyMap<String, W>, ParseError> {
+    let mut graph: AdjacencyMap<String, W> = HashMap::new();
+    // The node and arc counts from the problem line, once we have seen it.
+    let mut sizes: Option<(usize, usize)> = None;
+    let mut arcs = 0;
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        match fields.as_slice() {
+            [] | ["c", ..] => {}
+            ["p", "sp", nodes, arc_count] => {
+                if sizes.is_some() {
+                    return error(line, "more than one problem line");
+                }
+                let (Ok(nodes), Ok(arc_count)) = (nodes.parse(), arc_count.parse()) else {
+                    return error(line, "the node and arc counts must be numbers");
+                };
+                for node in 1..=nodes {
+                    graph.insert(node.to_string(), vec![]);
+                }
+                sizes = Some((nodes, arc_count));
+            }
+            ["p", ..] => return error(line, "expected \"p sp <nodes> <arcs>\""),
+            ["a", from, to, w] => {
+                let Some((nodes, _)) = sizes else {
+                    return error(line, "arc before the problem line");
+                };
+                for node in [from, to] {
+                    if !node
+                        .parse::<usize>()
+                        .is_ok_and(|n| (1..=nodes).contains(&n))
+                    {
+                        return error(
+                            line,
+                            format!("{:?} is not a node between 1 and {}", node, nodes),
+                        );
+                    }
+                }
+                add_edge(&mut graph, from, to, weight(line, w)?);
+                arcs += 1;
+            }
+            ["a", ..] => return error(line, "expected \"a <from> <to> <weight>\""),
+            [other, ..] => return error(line, format!("unknown line type {:?}", other)),
+        }
+    }
+    match sizes {
+        None => error(input.lines().count(), "no problem line"),
+        Some((_, expected)) if expected != arcs => error(
+            input.lines().count(),
+            format!(
+                "the problem line says {} arcs but there are {}",
+                expected, arcs
+            ),
+        ),
+        Some(_) => Ok(graph),
+    }
+}
+
+/// Reads `from,to,weight` lines. A first line whose weight has no digits in it
+/// at all, like `src,dst,weight`, is taken to be a header and skipped; one
+/// that only fails to parse, like `a,b,-1` for unsigned weights, is an error.
+pub fn parse_csv<W: FromStr>(input: &str) -> Result<AdjacencyMap<String, W>, ParseError> {
+    let mut graph: AdjacencyMap<String, W> = HashMap::new();
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        let [from, to, w] = fields.as_slice() else {
+            return error(
+                line,
+                format!("expected 3 fields, from,to,weight, not {}", fields.len()),
+            );
+        };
+        if from.is_empty() || to.is_empty() {
+            return error(line, "node names can't be empty");
+        }
+        let w = match weight(line, w) {
+            Ok(w) => w,
+            Err(_) if line == 1 && !w.chars().any(|c| c.is_ascii_digit()) => continue,
+            Err(e) => return Err(e),
+        };
+        add_edge(&mut graph, from, to, w);
+    }
+    Ok(graph)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    // An ID that wasn't in quotes, so it might be a keyword.
+    Word(String),
+    Quoted(String),
+    // -> or --
+    EdgeOp(&'static str),
+    Punct(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let start = line;
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        }
+                        None => return error(start, "unterminated comment"),
+                    }
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.next_if_eq(&'"').is_some() => text.push('"'),
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        }
+                        None => return error(start, "unterminated string"),
+                    }
+                }
+                tokens.push((Token::Quoted(text), start));
+            }
+            '-' if chars.next_if_eq(&'>').is_some() => tokens.push((Token::EdgeOp("->"), line)),
+            '-' if chars.next_if_eq(&'-').is_some() => tokens.push((Token::EdgeOp("--"), line)),
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' => tokens.push((Token::Punct(c), line)),
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let mut text = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    text.push(c);
+                }
+                tokens.push((Token::Word(text), line));
+            }
+            c => return error(line, format!("unexpected {:?}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+// Walks the tokens of a DOT file.
+struct DotParser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    // The line of the next token, or of the last one at the end.
+    fn line(&self) -> usize {
+        match self.tokens.get(self.position).or(self.tokens.last()) {
+            Some((_, line)) => *line,
+            None => 1,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            error(self.line(), format!("expected {:?}", c))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn id(&mut self) -> Result<String, ParseError> {
+        match self.next() {
+            Some(Token::Word(text)) | Some(Token::Quoted(text)) => Ok(text),
+            _ => {
+                self.position -= 1;
+                error(self.line(), "expected a name")
+            }
+        }
+    }
+
+    // Any number of [a=b, c=d] lists, run together.
+    fn attributes(&mut self) -> Result<HashMap<String, String>, ParseError> {
+        let mut attributes = HashMap::new();
+        while self.eat('[') {
+            while !self.eat(']') {
+                let key = self.id()?;
+                self.expect('=')?;
+                let value = self.id()?;
+                attributes.insert(key, value);
+                if !self.eat(',') {
+                    self.eat(';');
+                }
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// Reads a graph written in (a subset of) Graphviz's DOT language. An
+/// undirected `graph` gets an edge each way for every `--`.
+pub fn parse_dot<W: FromStr>(input: &str) -> Result<AdjacencyMap<String, W>, ParseError> {
+    let mut parser = DotParser {
+        tokens: tokenize(input)?,
+        position: 0,
+    };
+    let mut graph: AdjacencyMap<String, W> = HashMap::new();
+
+    if parser.is_keyword("strict") {
+        parser.next();
+    }
+    let directed = if parser.is_keyword("digraph") {
+        true
+    } else if parser.is_keyword("graph") {
+        false
+    } else {
+        return error(parser.line(), "expected graph or digraph");
+    };
+    parser.next();
+    if !parser.eat('{') {
+        parser.id()?;
+        parser.expect('{')?;
+    }
+    let edge_op = if directed { "->" } else { "--" };
+    let mut default_weight: Option<String> = None;
+
+    while !parser.eat('}') {
+        let line = parser.line();
+        if parser.peek().is_none() {
+            return error(line, "expected '}'");
+        }
+        if parser.is_keyword("subgraph") || parser.peek() == Some(&Token::Punct('{')) {
+            return error(line, "subgraphs are not supported");
+        }
+        if parser.is_keyword("graph") || parser.is_keyword("node") || parser.is_keyword("edge") {
+            let is_edge = parser.is_keyword("edge");
+            parser.next();
+            let attributes = parser.attributes()?;
+            if is_edge {
+                if let Some(w) = attributes.get("weight").or(attributes.get("label")) {
+                    default_weight = Some(w.clone());
+                }
+            }
+            parser.eat(';');
+            continue;
+        }
+
+        let mut nodes = vec![parser.id()?];
+        if parser.eat('=') {
+            // An attribute of the whole graph.
+            parser.id()?;
+            parser.eat(';');
+            continue;
+        }
+        while let Some(Token::EdgeOp(op)) = parser.peek() {
+            if *op != edge_op {
+                return error(
+                    parser.line(),
+                    format!("{} in a {}", op, if directed { "digraph" } else { "graph" }),
+                );
+            }
+            parser.next();
+            nodes.push(parser.id()?);
+        }
+        let attributes = parser.attributes()?;
+        parser.eat(';');
+        if nodes.len() == 1 {
+            graph.entry(nodes.pop().unwrap()).or_default();
+            continue;
+        }
+        let text = attributes
+            .get("weight")
+            .or(attributes.get("label"))
+            .or(default_weight.as_ref())
+            .map_or("1", String::as_str);
+        for pair in nodes.windows(2) {
+            add_edge(&mut graph, &pair[0], &pair[1], weight(line, text)?);
+            if !directed {
+                add_edge(&mut graph, &pair[1], &pair[0], weight(line, text)?);
+            }
+        }
+    }
+    if parser.peek().is_some() {
+        return error(parser.line(), "expected the end of the file");
+    }
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The edges as sorted (from, to, weight) triples, and the nodes.
+    fn edges(graph: &AdjacencyMap<String, u64>) -> (Vec<(&str, &str, u64)>, Vec<&str>) {
+        let mut edges: Vec<(&str, &str, u64)> = graph
+            .iter()
+            .flat_map(|(from, list)| {
+                list.iter()
+                    .map(move |edge| (from.as_str(), edge.dest.as_str(), edge.w))
+            })
+            .collect();
+        edges.sort();
+        let mut nodes: Vec<&str> = graph.keys().map(String::as_str).collect();
+        nodes.sort();
+        (edges, nodes)
+    }
+
+    #[test]
+    fn test_dimacs() {
+        let input = "c A tiny graph\nc\np sp 4 3\na 1 2 7\na 2 3 1\n\na 1 3 9\n";
+        let graph = parse_dimacs(input).unwrap();
+        assert_eq!(
+            edges(&graph),
+            (
+                vec![("1", "2", 7), ("1", "3", 9), ("2", "3", 1)],
+                vec!["1", "2", "3", "4"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_dimacs_errors() {
+        let message = |input: &str| parse_dimacs::<u64>(input).unwrap_err().to_string();
+        assert_eq!(message("a 1 2 3\n"), "line 1: arc before the problem line");
+        assert_eq!(
+            message("p sp 2 1\na 1 3 1\n"),
+            "line 2: \"3\" is not a node between 1 and 2"
+        );
+        assert_eq!(
+            message("p sp 2 1\na 1 2 x\n"),
+            "line 2: \"x\" is not a valid weight"
+        );
+        assert_eq!(
+            message("p sp 2 2\na 1 2 1\n"),
+            "line 2: the problem line says 2 arcs but there are 1"
+        );
+        assert_eq!(message("c nothing\n"), "line 1: no problem line");
+    }
+
+    #[test]
+    fn test_csv() {
+        let graph = parse_csv("src,dst,weight\na, b, 3\n\nb,c,4\n").unwrap();
+        assert_eq!(
+            edges(&graph),
+            (vec![("a", "b", 3), ("b", "c", 4)], vec!["a", "b", "c"])
+        );
+        assert_eq!(
+            parse_csv::<u64>("a,b,3\nb,c\n").unwrap_err().to_string(),
+            "line 2: expected 3 fields, from,to,weight, not 2"
+        );
+        assert_eq!(
+            parse_csv::<u64>("a,b,3\nb,c,-1\n").unwrap_err().to_string(),
+            "line 2: \"-1\" is not a valid weight"
+        );
+        // A bad weight on the first line is still a bad weight, not a header.
+        assert_eq!(
+            parse_csv::<u64>("a,b,-1\nb,c,4\n").unwrap_err().to_string(),
+            "line 1: \"-1\" is not a valid weight"
+        );
+        assert_eq!(
+            parse_csv::<u64>("a,b,1.5\n").unwrap_err().to_string(),
+            "line 1: \"1.5\" is not a valid weight"
+        );
+        // Negative weights are fine if the weight type allows them.
+        assert_eq!(parse_csv::<i64>("a,b,-1\n").unwrap()["a"][0].w, -1);
+    }
+
+    #[test]
+    fn test_dot() {
+        let input = r#"
+            // Roads between towns.
+            strict digraph "roads" {
+                rankdir = LR;
+                node [shape=box]
+                edge [weight=5]
+                a -> b [weight=2, color=red];
+                b -> c -> "d e" [label="3"]
+                a -> c  # uses the default
+                lonely
+                /* a comment
+                   over two lines */
+            }
+        "#;
+        let graph = parse_dot(input).unwrap();
+        assert_eq!(
+            edges(&graph),
+            (
+                vec![("a", "b", 2), ("a", "c", 5), ("b", "c", 3), ("c", "d e", 3)],
+                vec!["a", "b", "c", "d e", "lonely"]
+            )
+        );
+
+        let graph = parse_dot("graph { x -- y }").unwrap();
+        assert_eq!(
+            edges(&graph),
+            (vec![("x", "y", 1), ("y", "x", 1)], vec!["x", "y"])
+        );
+    }
+
+    #[test]
+    fn test_dot_errors() {
+        let message = |input: &str| parse_dot::<u64>(input).unwrap_err().to_string();
+        assert_eq!(message("digraph {\n a -- b\n}"), "line 2: -- in a digraph");
+        assert_eq!(
+            message("digraph {\n subgraph { a }\n}"),
+            "line 2: subgraphs are not supported"
+        );
+        assert_eq!(message("digraph {\n a -> b"), "line 2: expected '}'");
+        assert_eq!(message("tree { }"), "line 1: expected graph or digraph");
+        assert_eq!(message("digraph { \"a }"), "line 1: unterminated string");
+        assert_eq!(
+            message("digraph {\n\n a -> b [weight=heavy] }"),
+            "line 3: \"heavy\" is not a valid weight"
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!("dot".parse(), Ok(Format::Dot));
+        assert!("xml".parse::<Format>().is_err());
+        assert_eq!(
+            Format::from_path(Path::new("usa/ny.gr")),
+            Some(Format::Dimacs)
+        );
+        assert_eq!(Format::from_path(Path::new("graph.txt")), None);
+        let graph: AdjacencyMap<String, u64> = parse(Format::Csv, "a,b,1").unwrap();
+        assert_eq!(graph.len(), 2);
+    }
+}
@@ -0,0 +1,219 @@
+// Minimum spanning trees, by Prim's algorithm (grow one tree outwards from a
+// node, always taking the cheapest edge that reaches a new node) and by
+// Kruskal's (take the edges cheapest first, skipping any that would close a
+// loop). Both work on undirected graphs, so they want every edge listed in
+// both directions, the way parse_dot reads a `graph`. If the graph is in more
+// than one piece, you get a tree for each piece.
+
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::error::SearchError;
+use crate::graph::{Edge, Graph};
+use crate::union_find::UnionFind;
+use crate::weight::Weight;
+
+/// The edges of a minimum spanning forest, each as the node it leaves and the
+/// edge itself, and their total weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanningForest<T, W> {
+    pub edges: Vec<(T, Edge<T, W>)>,
+    pub weight: W,
+}
+
+impl<T: Clone, W: Weight> SpanningForest<T, W> {
+    fn new() -> Self {
+        SpanningForest {
+            edges: vec![],
+            weight: W::zero(),
+        }
+    }
+
+    fn add(&mut self, from: T, edge: Edge<T, W>) -> Result<(), SearchError<T>> {
+        self.weight = self
+            .weight
+            .checked_add(&edge.w)
+            .ok_or_else(|| SearchError::Overflow {
+                from: from.clone(),
+                to: edge.dest.clone(),
+            })?;
+        self.edges.push((from, edge));
+        Ok(())
+    }
+}
+
+/// A minimum spanning forest by Prim's algorithm. `nodes` has to list every
+/// node; the trees are grown from them in that order.
+pub fn prim<G, T, W, I>(graph: &G, nodes: I) -> Result<SpanningForest<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+    I: IntoIterator<Item = T>,
+{
+    let mut forest = SpanningForest::new();
+    let mut in_tree: HashSet<T> = HashSet::new();
+    // Edges out of the tree so far, cheapest first thanks to Edge's ordering,
+    // with the node they leave from.
+    let mut queue: BinaryHeap<(Edge<T, W>, T)> = BinaryHeap::new();
+    for root in nodes {
+        if !in_tree.insert(root.clone()) {
+            continue;
+        }
+        queue.extend(graph.neighbors(&root).map(|edge| (edge, root.clone())));
+        while let Some((edge, from)) = queue.pop() {
+            if !in_tree.insert(edge.dest.clone()) {
+                continue;
+            }
+            queue.extend(
+                graph
+                    .neighbors(&edge.dest)
+                    .filter(|next| !in_tree.contains(&next.dest))
+                    .map(|next| (next, edge.dest.clone())),
+            );
+            forest.add(from, edge)?;
+        }
+    }
+    Ok(forest)
+}
+
+/// A minimum spanning forest by Kruskal's algorithm. `nodes` has to list every
+/// node with edges out of it.
+pub fn kruskal<G, T, W, I>(graph: &G, nodes: I) -> Result<SpanningForest<T, W>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Hash + Clone,
+    W: Weight,
+    I: IntoIterator<Item = T>,
+{
+    let mut numbers: HashMap<T, usize> = HashMap::new();
+    let mut sets = UnionFind::new(0);
+    let mut number = |node: &T, sets: &mut UnionFind| -> usize {
+        *numbers.entry(node.clone()).or_insert_with(|| sets.push())
+    };
+    let mut edges: Vec<(usize, usize, T, Edge<T, W>)> = vec![];
+    for from in nodes {
+        let a = number(&from, &mut sets);
+        for edge in graph.neighbors(&from) {
+            let b = number(&edge.dest, &mut sets);
+            edges.push((a, b, from.clone(), edge));
+        }
+    }
+    // A stable sort, so ties go to whichever edge was listed first.
+    edges.sort_by(|x, y| x.3.w.cmp(&y.3.w));
+
+    let mut forest = SpanningForest::new();
+    for (a, b, from, edge) in edges {
+        if sets.union(a, b) {
+            forest.add(from, edge)?;
+        }
+    }
+    Ok(forest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::AdjacencyMap;
+
+    // Lists each edge both ways.
+    fn undirected(edges: &[(char, char, u64)]) -> AdjacencyMap<char> {
+        let mut graph: AdjacencyMap<char> = HashMap::new();
+        for &(a, b, w) in edges {
+            graph.entry(a).or_default().push(Edge { dest: b, w });
+            graph.entry(b).or_default().push(Edge { dest: a, w });
+        }
+        graph
+    }
+
+    // The spanning tree edges with the smaller end first, sorted.
+    fn pairs(forest: &SpanningForest<char, u64>) -> Vec<(char, char)> {
+        let mut pairs: Vec<(char, char)> = forest
+            .edges
+            .iter()
+            .map(|(from, edge)| (*from.min(&edge.dest), *from.max(&edge.dest)))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_example() {
+        // The example from the Wikipedia article on Kruskal's algorithm.
+        let graph = undirected(&[
+            ('a', 'b', 7),
+            ('a', 'd', 5),
+            ('b', 'c', 8),
+            ('b', 'd', 9),
+            ('b', 'e', 7),
+            ('c', 'e', 5),
+            ('d', 'e', 15),
+            ('d', 'f', 6),
+            ('e', 'f', 8),
+            ('e', 'g', 9),
+            ('f', 'g', 11),
+        ]);
+        let expected = vec![
+            ('a', 'b'),
+            ('a', 'd'),
+            ('b', 'e'),
+            ('c', 'e'),
+            ('d', 'f'),
+            ('e', 'g'),
+        ];
+        for forest in [
+            prim(&graph, "abcdefg".chars()).unwrap(),
+            kruskal(&graph, "abcdefg".chars()).unwrap(),
+        ] {
+            assert_eq!(forest.weight, 39);
+            assert_eq!(pairs(&forest), expected);
+        }
+    }
+
+    #[test]
+    fn test_forest() {
+        // Two pieces and a node on its own.
+        let graph = undirected(&[('a', 'b', 1), ('b', 'c', 2), ('a', 'c', 3), ('x', 'y', 4)]);
+        for forest in [
+            prim(&graph, "abcxyz".chars()).unwrap(),
+            kruskal(&graph, "abcxyz".chars()).unwrap(),
+        ] {
+            assert_eq!(forest.weight, 7);
+            assert_eq!(pairs(&forest), vec![('a', 'b'), ('b', 'c'), ('x', 'y')]);
+        }
+    }
+
+    #[test]
+    fn test_random_graphs_agree() {
+        let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut random = move |below: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % below
+        };
+        for _ in 0..30 {
+            let nodes: Vec<char> = ('a'..='z').take(1 + random(20) as usize).collect();
+            let edges: Vec<(char, char, u64)> = (0..random(60))
+                .map(|_| {
+                    let pick = |n: u64| nodes[n as usize % nodes.len()];
+                    (pick(random(100)), pick(random(100)), random(50))
+                })
+                .collect();
+            let graph = undirected(&edges);
+            let by_prim = prim(&graph, nodes.iter().copied()).unwrap();
+            let by_kruskal = kruskal(&graph, nodes.iter().copied()).unwrap();
+            assert_eq!(by_prim.weight, by_kruskal.weight);
+            assert_eq!(by_prim.edges.len(), by_kruskal.edges.len());
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let graph = undirected(&[('a', 'b', u64::MAX), ('b', 'c', 1)]);
+        assert!(matches!(
+            kruskal(&graph, "abc".chars()),
+            Err(SearchError::Overflow { .. })
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+
+/// What an edge can cost. Distances are sums of weights, so all a weight needs
+/// is an order, a starting point, and an addition that can say no instead of
+/// overflowing.
+pub trait Weight: Clone + Ord {
+    /// The cost of going nowhere.
+    fn zero() -> Self;
+
+    /// `self + other`, or None if that does not fit.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+/// A `Weight` that can also be taken away, which `johnson` needs to reweight
+/// the edges.
+pub trait Subtract: Weight {
+    /// `self - other`, or None if that does not fit.
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! integer_weight {
+    ($($int:ty),*) => {
+        $(
+            impl Weight for $int {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$int>::checked_add(*self, *other)
+                }
+            }
+
+            impl Subtract for $int {
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$int>::checked_sub(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+integer_weight!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// An `f64` that can be a `Weight`. It is ordered by `f64::total_cmp`, and an
+/// addition that comes out infinite or NaN counts as an overflow.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderedFloat(pub f64);
+
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Weight for OrderedFloat {
+    fn zero() -> Self {
+        OrderedFloat(0.0)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let sum = self.0 + other.0;
+        if sum.is_finite() {
+            Some(OrderedFloat(sum))
+        } else {
+            None
+        }
+    }
+}
+
+impl Subtract for OrderedFloat {
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let difference = self.0 - other.0;
+        if difference.is_finite() {
+            Some(OrderedFloat(difference))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_add() {
+        assert_eq!(Weight::checked_add(&200u8, &55), Some(255));
+        assert_eq!(Weight::checked_add(&200u8, &56), None);
+        assert_eq!(Weight::checked_add(&-5i32, &3), Some(-2));
+        assert_eq!(
+            OrderedFloat(0.5).checked_add(&OrderedFloat(0.25)),
+            Some(OrderedFloat(0.75))
+        );
+        assert_eq!(
+            OrderedFloat(f64::MAX).checked_add(&OrderedFloat(f64::MAX)),
+            None
+        );
+        assert_eq!(Subtract::checked_sub(&3u32, &4), None);
+        assert_eq!(Subtract::checked_sub(&3i32, &4), Some(-1));
+    }
+
+    #[test]
+    fn test_float_order() {
+        let mut costs = vec![OrderedFloat(2.5), OrderedFloat(-1.0), OrderedFloat(0.0)];
+        costs.sort();
+        assert_eq!(
+            costs,
+            vec![OrderedFloat(-1.0), OrderedFloat(0.0), OrderedFloat(2.5)]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+// Yen's algorithm for the k shortest loopless paths. Having found the i
+// shortest paths, every candidate for the next one leaves one of them partway
+// along (the spur node) and takes a different edge from any path found so far
+// that shares the way there (the root). So for each node of the last path
+// found, we search from it with the root's nodes and those edges taken out,
+// and keep the best of all the candidates that turn up.
+
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+use crate::dijkstra::{find_path, SearchOutcome};
+use crate::error::SearchError;
+use crate::graph::{Edge, Graph};
+use crate::weight::Weight;
+
+/// Up to `k` shortest paths from `source` to `target` that never visit a node
+/// twice, shortest first, each with what it costs. Paths are written out the
+/// way `traceback` does it. Fewer than `k` come back if there aren't that
+/// many. Parallel edges count as one edge, with the lowest weight among them.
+#[allow(clippy::type_complexity)]
+pub fn k_shortest_paths<G, T, W>(
+    graph: &G,
+    source: T,
+    target: T,
+    k: usize,
+) -> Result<Vec<(Vec<T>, W)>, SearchError<T>>
+where
+    G: Graph<Node = T, Weight = W>,
+    T: Eq + Ord + Hash + Clone,
+    W: Weight,
+{
+    let mut found: Vec<(Vec<T>, W)> = vec![];
+    if k == 0 {
+        return Ok(found);
+    }
+    match find_path(graph, source, |node| *node == target, None)? {
+        SearchOutcome::Found { path, cost } => found.push((path, cost)),
+        _ => return Ok(found),
+    }
+    // Ordered by cost first, which is what we want to pop, and a set so the
+    // same candidate turning up twice only counts once.
+    let mut candidates: BTreeSet<(W, Vec<T>)> = BTreeSet::new();
+
+    while found.len() < k {
+        let (last, _) = found.last().unwrap();
+        let mut root_cost = W::zero();
+        for i in 0..last.len() - 1 {
+            let root = &last[..=i];
+            let spur = &last[i];
+            let mut without = Without {
+                graph,
+                nodes: root[..i].iter().cloned().collect(),
+                edges: HashSet::new(),
+            };
+            for (path, _) in found.iter() {
+                if path.len() > i + 1 && path[..=i] == *root {
+                    without.edges.insert((path[i].clone(), path[i + 1].clone()));
+                }
+            }
+            if let SearchOutcome::Found { path, cost } =
+                find_path(&without, spur.clone(), |node| *node == target, None)?
+            {
+                let cost = root_cost
+                    .checked_add(&cost)
+                    .ok_or_else(|| SearchError::Overflow {
+                        from: spur.clone(),
+                        to: target.clone(),
+                    })?;
+                let mut whole = root[..i].to_vec();
+                whole.extend(path);
+                if !found.iter().any(|(path, _)| *path == whole) {
+                    candidates.insert((cost, whole));
+                }
+            }
+            let step = edge_weight(graph, &last[i], &last[i + 1])
+                .expect("every step of a path we found is an edge");
+            root_cost = root_cost
+                .checked_add(&step)
+                .ok_or_else(|| SearchError::Overflow {
+                    from: last[i].clone(),
+                    to: last[i + 1].clone(),
+                })?;
+        }
+        match candidates.pop_first() {
+            Some((cost, path)) => found.push((path, cost)),
+            None => break,
+        }
+    }
+    Ok(found)
+}
+
+// The lowest weight of the edges from `from` to `to`.
+fn edge_weight<G: Graph>(graph: &G, from: &G::Node, to: &G::Node) -> Option<G::Weight>
+where
+    G::Node: Eq,
+{
+    graph
+        .neighbors(from)
+        .filter(|edge| edge.dest == *to)
+        .map(|edge| edge.w)
+        .min()
+}
+
+// The graph inside with some nodes, and any edge into them, taken out, and
+// some edges too.
+struct Without<'a, G: Graph> {
+    graph: &'a G,
+    nodes: HashSet<G::Node>,
+    edges: HashSet<(G::Node, G::Node)>,
+}
+
+impl<G> Graph for Without<'_, G>
+where
+    G: Graph,
+    G::Node: Eq + Hash + Clone,
+{
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn neighbors(&self, node: &G::Node) -> impl Iterator<Item = Edge<G::Node, G::Weight>> {
+        // Edges out of a removed node don't matter: nothing can get to it.
+        self.graph.neighbors(node).filter(move |edge| {
+            !self.nodes.contains(&edge.dest)
+                && !self.edges.contains(&(node.clone(), edge.dest.clone()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // The example from the Wikipedia article on Yen's algorithm.
+    fn example() -> HashMap<char, Vec<Edge<char>>> {
+        let edges = [
+            ('C', 'D', 3),
+            ('C', 'E', 2),
+            ('D', 'F', 4),
+            ('E', 'D', 1),
+            ('E', 'F', 2),
+            ('E', 'G', 3),
+            ('F', 'G', 2),
+            ('F', 'H', 1),
+            ('G', 'H', 2),
+        ];
+        let mut graph: HashMap<char, Vec<Edge<char>>> = HashMap::new();
+        for (from, dest, w) in edges {
+            graph.entry(from).or_default().push(Edge { dest, w });
+        }
+        graph
+    }
+
+    #[test]
+    fn test_wikipedia_example() {
+        let paths = k_shortest_paths(&example(), 'C', 'H', 3).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                (vec!['C', 'E', 'F', 'H'], 5),
+                (vec!['C', 'E', 'G', 'H'], 7),
+                (vec!['C', 'D', 'F', 'H'], 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runs_out_of_paths() {
+        // There are only seven loopless paths from C to H.
+        let paths = k_shortest_paths(&example(), 'C', 'H', 100).unwrap();
+        assert_eq!(paths.len(), 7);
+        for pair in paths.windows(2) {
+            assert!(pair[0].1 <= pair[1].1);
+        }
+        let distinct: HashSet<&Vec<char>> = paths.iter().map(|(path, _)| path).collect();
+        assert_eq!(distinct.len(), 7);
+        for (path, _) in paths.iter() {
+            let nodes: HashSet<&char> = path.iter().collect();
+            assert_eq!(nodes.len(), path.len());
+        }
+
+        assert!(k_shortest_paths(&example(), 'H', 'C', 3)
+            .unwrap()
+            .is_empty());
+        assert!(k_shortest_paths(&example(), 'C', 'H', 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            k_shortest_paths(&example(), 'C', 'C', 3).unwrap(),
+            vec![(vec!['C'], 0)]
+        );
+    }
+}